@@ -0,0 +1,168 @@
+// Functions compiled by the system C compiler and linked with test.c, to
+// check that qcc follows the System V calling convention when they call each
+// other.
+
+typedef struct { char a; } S1;
+typedef struct { char a, b, c; } S3;
+typedef struct { int a; char b; short c; } S8;
+typedef struct { int a, b, c; } S12;
+typedef struct { long a; char b; } S16;
+typedef struct { long a, b, c; } S24;
+typedef struct { unsigned a:3; int b:5; char c; long d:40; unsigned e:1; } BF;
+
+int gcc_s1(S1 x) { return x.a; }
+int gcc_s3(S3 x) { return x.a * 100 + x.b * 10 + x.c; }
+long gcc_s8(S8 x) { return x.a * 100 + x.b * 10 + x.c; }
+long gcc_s12(S12 x) { return x.a * 100 + x.b * 10 + x.c; }
+long gcc_s16(S16 x) { return x.a * 10 + x.b; }
+long gcc_s24(S24 x) { return x.a * 100 + x.b * 10 + x.c; }
+
+// The struct no longer fits in the remaining registers and goes on the
+// stack, while later scalars still use registers.
+long gcc_spill(long a, long b, long c, long d, long e, S16 x, long f) {
+    return a + b + c + d + e + x.a * 1000 + x.b * 100 + f * 10;
+}
+
+long gcc_many(long a, long b, long c, long d, long e, long f, long g, S3 x, long h) {
+    return a + b + c + d + e + f + g * 10 + x.a * 100 + x.c * 1000 + h * 10000;
+}
+
+S1 gcc_ret_s1(void) { return (S1){ 7 }; }
+S3 gcc_ret_s3(void) { return (S3){ 1, 2, 3 }; }
+S12 gcc_ret_s12(void) { return (S12){ 4, 5, 6 }; }
+S16 gcc_ret_s16(void) { return (S16){ 123456789012, 9 }; }
+S24 gcc_ret_s24(long n) { return (S24){ n, n * 2, n * 3 }; }
+
+S3 qcc_ret_s3(void);
+S12 qcc_ret_s12(void);
+S24 qcc_ret_s24(long n);
+long qcc_s24(S24 x);
+long qcc_spill(long a, long b, long c, long d, long e, S16 x, long f);
+
+long gcc_call_qcc(void) {
+    S3 s3 = qcc_ret_s3();
+    S12 s12 = qcc_ret_s12();
+    S24 s24 = qcc_ret_s24(2);
+    return s3.a + s3.b + s3.c + s12.a + s12.b + s12.c + s24.a + s24.b + s24.c;
+}
+
+long gcc_call_qcc_args(void) {
+    S24 s24 = { 1, 2, 3 };
+    S16 s16 = { 4, 5 };
+    return qcc_s24(s24) * 100000 + qcc_spill(0, 0, 0, 0, 1, s16, 6);
+}
+
+// Only the low byte of a _Bool argument is defined, so the callee must
+// ignore whatever the caller leaves in the bits above it.
+_Bool bool_not(_Bool x);
+long bool_stack(long a, long b, long c, long d, long e, long f, _Bool g);
+
+long gcc_call_qcc_bool(void) {
+    long (*not)(long) = (long (*)(long))bool_not;
+    long (*stack)(long, long, long, long, long, long, long) =
+        (long (*)(long, long, long, long, long, long, long))bool_stack;
+    return (not(0x100) & 1) * 10 + stack(0, 0, 0, 0, 0, 0, 0x200);
+}
+
+// Bit-fields have to be packed the same way by both compilers.
+void gcc_bf_fill(BF *p) {
+    p->a = 5;
+    p->b = -7;
+    p->c = 9;
+    p->d = -123456789012;
+    p->e = 1;
+}
+
+long gcc_bf_check(BF *p) {
+    return p->a == 6 && p->b == 11 && p->c == 3 && p->d == 98765432109 && p->e == 0;
+}
+
+// Declared in test.c as `extern int gcc_tbl[];`.
+int gcc_tbl[] = { 10, 20, 30, 40 };
+
+// Floating values are passed in SSE registers, and so are the eightbytes of
+// a struct made of floating members only.
+typedef struct { double a; } SD;
+typedef struct { float a, b; } SFF;
+typedef struct { float a; int b; double c; } SFID;
+typedef struct { double a; long b; } SDL;
+
+double gcc_fadd(double a, float b, int c) { return a + b + c; }
+float gcc_fmul(float a, float b) { return a * b; }
+
+// The last two arguments no longer fit in the SSE registers.
+double gcc_fmany(double a, double b, double c, double d, double e, double f, double g,
+                 double h, double i, float j) {
+    return a + b * 2 + c * 3 + d * 4 + e * 5 + f * 6 + g * 7 + h * 8 + i * 9 + j * 10;
+}
+
+double gcc_fmix(int a, double b, long c, float d, char e, double f) {
+    return a * 100000 + b * 10000 + c * 1000 + d * 100 + e * 10 + f;
+}
+
+double gcc_sd(SD x) { return x.a; }
+double gcc_sff(SFF x) { return x.a * 10 + x.b; }
+double gcc_sfid(SFID x) { return x.a * 100 + x.b * 10 + x.c; }
+double gcc_sdl(SDL x) { return x.a * 10 + x.b; }
+
+SFF gcc_ret_sff(void) { return (SFF){ 1.5, 2.5 }; }
+SFID gcc_ret_sfid(void) { return (SFID){ 3.5, 4, 5.5 }; }
+SDL gcc_ret_sdl(void) { return (SDL){ 6.5, 7 }; }
+
+// A variadic callee only saves the SSE argument registers if al says they
+// are used.
+double gcc_vsum(int n, ...) {
+    __builtin_va_list ap;
+    __builtin_va_start(ap, n);
+    double sum = 0;
+    for (int i = 0; i < n; i++)
+        sum += __builtin_va_arg(ap, double);
+    __builtin_va_end(ap);
+    return sum;
+}
+
+double qcc_fmix(int a, double b, long c, float d, char e, double f);
+SFID qcc_ret_sfid(void);
+double qcc_sfid(SFID x);
+
+double gcc_call_qcc_float(void) {
+    SFID x = qcc_ret_sfid();
+    return qcc_fmix(1, 2, 3, 4, 5, 6) + x.a + x.b + x.c + qcc_sfid((SFID){ 1, 2, 3 }) * 1000000;
+}
+
+typedef struct { long double a; } SLD;
+typedef struct { char a; long double b; } SCLD;
+
+// long double arguments always go on the stack, in 16-byte aligned slots,
+// and the result comes back in st(0).
+long double gcc_ld_add(long double a, double b, long double c) { return a + b * 10 + c * 100; }
+long double gcc_ld_many(int a, long double b, double c, int d, long double e, float f) {
+    return a + b * 10 + c * 100 + d * 1000 + e * 10000 + f * 100000;
+}
+long double gcc_sld(SLD x) { return x.a; }
+long double gcc_scld(SCLD x) { return x.a * 10 + x.b; }
+SLD gcc_ret_sld(void) { return (SLD){ 2.5L }; }
+SCLD gcc_ret_scld(void) { return (SCLD){ 3, 4.5L }; }
+
+long double gcc_vsum_ld(int n, ...) {
+    __builtin_va_list ap;
+    __builtin_va_start(ap, n);
+    long double sum = 0;
+    for (int i = 0; i < n; i++)
+        sum += __builtin_va_arg(ap, long double);
+    __builtin_va_end(ap);
+    return sum;
+}
+
+long double qcc_ld_many(int a, long double b, double c, int d, long double e, float f);
+SLD qcc_ret_sld(void);
+long double qcc_scld(SCLD x);
+
+long double gcc_call_qcc_ld(void) {
+    return qcc_ld_many(1, 2, 3, 4, 5, 6) + qcc_ret_sld().a * 1000000 + qcc_scld((SCLD){ 7, 8 }) * 10000000;
+}
+
+// Constants gcc rounds from their digits to the full long double precision.
+long double gcc_ld_tenth(void) { return 0.1L; }
+long double gcc_ld_tiny(void) { return 1e-4950L; }
+long double gcc_ld_huge(void) { return 1e4000L; }
@@ -1,7 +1,487 @@
-use crate::{Function, Node, NodeKind, Tokens, TypeKind, Var};
+use crate::{Function, Member, Node, NodeKind, Tokens, Type, TypeKind, Var};
 
 const ARG_REG8: &[&str] = &["dil", "sil", "dl", "cl", "r8b", "r9b"];
+const ARG_REG16: &[&str] = &["di", "si", "dx", "cx", "r8w", "r9w"];
+const ARG_REG32: &[&str] = &["edi", "esi", "edx", "ecx", "r8d", "r9d"];
 const ARG_REG64: &[&str] = &["rdi", "rsi", "rdx", "rcx", "r8", "r9"];
+const FP_ARG_REGS: usize = 8;
+
+/// Returns the name of the `i`-th argument register sized for `size` bytes.
+fn arg_reg(i: usize, size: u64) -> &'static str {
+    match size {
+        1 => ARG_REG8[i],
+        2 => ARG_REG16[i],
+        4 => ARG_REG32[i],
+        _ => ARG_REG64[i],
+    }
+}
+
+/// Where an argument is passed under the System V x86-64 calling convention.
+enum ArgLoc {
+    /// In argument registers, one per eightbyte: the general-purpose ones
+    /// starting with `rdi` plus `gp`, and the SSE ones starting with `xmm`
+    /// plus `fp`. `eightbyte_classes` says which eightbyte goes where.
+    Reg { gp: usize, fp: usize },
+    /// In the argument area on the stack, at this offset from the stack
+    /// pointer at the call.
+    Stack(u64),
+}
+
+/// Returns whether every scalar of `ty`, placed at `offset`, that starts
+/// within the bytes `lo..hi` has a floating type.
+fn only_flonums(ty: &Type, lo: u64, hi: u64, offset: u64) -> bool {
+    match &ty.kind {
+        TypeKind::Struct { .. } | TypeKind::Union { .. } => ty
+            .members()
+            .unwrap()
+            .iter()
+            .all(|member| only_flonums(&member.ty, lo, hi, offset + member.offset)),
+        TypeKind::Array { base, len } => {
+            let size = base.size().unwrap();
+            (0..len.unwrap_or(0)).all(|i| only_flonums(base, lo, hi, offset + size * i))
+        }
+        _ => offset < lo || hi <= offset || ty.is_flonum(),
+    }
+}
+
+/// Returns, for each eightbyte of a value of type `ty` passed in registers,
+/// whether it is passed in an SSE register rather than a general-purpose
+/// one. That is the case for the eightbytes made up of floating members
+/// only.
+fn eightbyte_classes(ty: &Type) -> Vec<bool> {
+    if !ty.is_struct_or_union() {
+        return vec![ty.is_flonum()];
+    }
+    (0..ty.size().unwrap())
+        .step_by(8)
+        .map(|lo| only_flonums(ty, lo, lo + 8, 0))
+        .collect()
+}
+
+/// Assigns a location to each argument of the types `tys`, with the first
+/// `first_reg` general-purpose argument registers already taken. Returns the
+/// locations, the size of the stack argument area and the number of SSE
+/// registers used.
+///
+/// Integers and pointers take one general-purpose register each, and
+/// `float` and `double` values one SSE register each. A struct or union of up
+/// to 16 bytes is split into eightbytes, each passed in a register of its
+/// class, and goes on the stack as a whole if not enough registers are left
+/// for it. Larger ones, and `long double` values and anything containing
+/// them, are always copied onto the stack.
+fn classify_args<'a>(
+    tys: impl Iterator<Item = &'a Type>,
+    first_reg: usize,
+) -> (Vec<ArgLoc>, u64, usize) {
+    let mut gp = first_reg;
+    let mut fp = 0;
+    let mut stack_size: u64 = 0;
+    let mut locs = vec![];
+    for ty in tys {
+        let size = ty.size().unwrap();
+        if !ty.has_long_double() && (!ty.is_struct_or_union() || size <= 16) {
+            let classes = eightbyte_classes(ty);
+            let nfp = classes.iter().filter(|&&is_sse| is_sse).count();
+            let ngp = classes.len() - nfp;
+            if gp + ngp <= ARG_REG64.len() && fp + nfp <= FP_ARG_REGS {
+                locs.push(ArgLoc::Reg { gp, fp });
+                gp += ngp;
+                fp += nfp;
+                continue;
+            }
+        }
+
+        let align = ty.align().max(8);
+        stack_size = stack_size.div_ceil(align) * align;
+        locs.push(ArgLoc::Stack(stack_size));
+        stack_size += size.div_ceil(8) * 8;
+    }
+    (locs, stack_size, fp)
+}
+
+/// Formats the memory operand `[base + offset]`.
+fn addr(base: &str, offset: i64) -> String {
+    match offset {
+        0 => format!("[{}]", base),
+        offset if offset < 0 => format!("[{} - {}]", base, -offset),
+        offset => format!("[{} + {}]", base, offset),
+    }
+}
+
+/// Copies `size` bytes from `[src + src_offset]` to `[dst + dst_offset]`,
+/// using `r11` as scratch.
+fn copy_bytes(
+    dst: &str,
+    dst_offset: i64,
+    src: &str,
+    src_offset: i64,
+    size: u64,
+    asm: &mut Vec<String>,
+) {
+    for i in 0..size as i64 {
+        asm.push(format!("  mov r11b, {}", addr(src, src_offset + i)));
+        asm.push(format!("  mov {}, r11b", addr(dst, dst_offset + i)));
+    }
+}
+
+/// Loads the `size` bytes at `[base + offset]` into the low bytes of `reg`,
+/// without reading past them. `reg8` must name the low byte of `reg`.
+fn load_bytes(reg: &str, reg8: &str, base: &str, offset: i64, size: u64, asm: &mut Vec<String>) {
+    if size == 8 {
+        asm.push(format!("  mov {}, {}", reg, addr(base, offset)));
+        return;
+    }
+    for i in (0..size as i64).rev() {
+        asm.push(format!("  shl {}, 8", reg));
+        asm.push(format!("  mov {}, {}", reg8, addr(base, offset + i)));
+    }
+}
+
+/// Stores the low `size` bytes of `reg` to `[base + offset]`, clobbering
+/// `reg`. `reg8` must name the low byte of `reg`.
+fn store_bytes(reg: &str, reg8: &str, base: &str, offset: i64, size: u64, asm: &mut Vec<String>) {
+    if size == 8 {
+        asm.push(format!("  mov {}, {}", addr(base, offset), reg));
+        return;
+    }
+    for i in 0..size as i64 {
+        asm.push(format!("  mov {}, {}", addr(base, offset + i), reg8));
+        asm.push(format!("  shr {}, 8", reg));
+    }
+}
+
+/// Returns the name of `rax` sized for `size` bytes.
+fn rax(size: u64) -> &'static str {
+    match size {
+        1 => "al",
+        2 => "ax",
+        4 => "eax",
+        _ => "rax",
+    }
+}
+
+/// Replaces the address in `rax` with the scalar of type `ty` stored there,
+/// extended to 64 bits. A `float` is zero-extended from its 32 bits, and a
+/// `long double` is loaded into `st(0)` instead.
+fn load_scalar(ty: &Type, asm: &mut Vec<String>) {
+    match ty.kind {
+        TypeKind::Float { .. } => {
+            asm.push(String::from("  mov eax, DWORD PTR [rax]"));
+            return;
+        }
+        TypeKind::LongDouble { .. } => {
+            asm.push(String::from("  fld TBYTE PTR [rax]"));
+            return;
+        }
+        _ => (),
+    }
+    let insn = match (ty.size(), ty.is_unsigned) {
+        (Some(1), false) => "  movsx rax, BYTE PTR [rax]",
+        (Some(1), true) => "  movzx eax, BYTE PTR [rax]",
+        (Some(2), false) => "  movsx rax, WORD PTR [rax]",
+        (Some(2), true) => "  movzx eax, WORD PTR [rax]",
+        (Some(4), false) => "  movsxd rax, DWORD PTR [rax]",
+        (Some(4), true) => "  mov eax, DWORD PTR [rax]",
+        _ => "  mov rax, [rax]",
+    };
+    asm.push(String::from(insn));
+}
+
+/// Sign- or zero-extends a value of type `ty` held in the low bytes of `rax`
+/// to the whole register.
+///
+/// Integer values are always kept extended to 64 bits while they are on the
+/// stack, so arithmetic and comparisons can operate on full registers.
+fn extend(ty: &Type, asm: &mut Vec<String>) {
+    if !ty.is_integer() {
+        return;
+    }
+
+    let insn = match (ty.size(), ty.is_unsigned) {
+        (Some(1), false) => "  movsx rax, al",
+        (Some(1), true) => "  movzx eax, al",
+        (Some(2), false) => "  movsx rax, ax",
+        (Some(2), true) => "  movzx eax, ax",
+        (Some(4), false) => "  movsxd rax, eax",
+        (Some(4), true) => "  mov eax, eax",
+        _ => return,
+    };
+    asm.push(String::from(insn));
+}
+
+/// Converts the value in `reg` to `_Bool` in place: 1 if it is nonzero and 0
+/// otherwise. `reg8` must name the low byte of `reg`.
+fn to_bool(reg: &str, reg8: &str, asm: &mut Vec<String>) {
+    asm.push(format!("  cmp {}, 0", reg));
+    asm.push(format!("  setne {}", reg8));
+    asm.push(format!("  movzx {}, {}", reg, reg8));
+}
+
+/// Normalizes a `_Bool` argument in the byte register `reg8` to 0 or 1. Only
+/// the low 8 bits of an argument of type `_Bool` are defined by the ABI.
+fn bool_arg(reg8: &str, asm: &mut Vec<String>) {
+    asm.push(format!("  test {0}, {0}", reg8));
+    asm.push(format!("  setne {}", reg8));
+}
+
+/// Returns the number of bytes a value of type `ty` takes on the stack while
+/// expressions are evaluated: 16 for a `long double` and 8 for anything else.
+fn slot_size(ty: Option<&Type>) -> u64 {
+    match ty {
+        Some(ty) if ty.is_long_double() => 16,
+        _ => 8,
+    }
+}
+
+/// Pushes the value of type `ty` held in `rax`, or in `st(0)` for a
+/// `long double`.
+fn push(ty: Option<&Type>, asm: &mut Vec<String>) {
+    if ty.is_some_and(|ty| ty.is_long_double()) {
+        asm.push(String::from("  sub rsp, 16"));
+        asm.push(String::from("  fstp TBYTE PTR [rsp]"));
+    } else {
+        asm.push(String::from("  push rax"));
+    }
+}
+
+/// Pops a value of type `ty` into `rax`, or into `st(0)` for a `long double`.
+fn pop(ty: Option<&Type>, asm: &mut Vec<String>) {
+    if ty.is_some_and(|ty| ty.is_long_double()) {
+        asm.push(String::from("  fld TBYTE PTR [rsp]"));
+        asm.push(String::from("  add rsp, 16"));
+    } else {
+        asm.push(String::from("  pop rax"));
+    }
+}
+
+/// Returns the x87 80-bit extended precision representation of `val`, which
+/// holds every `double` exactly.
+pub(crate) fn x87_bytes(val: f64) -> [u8; 10] {
+    let bits = val.to_bits();
+    let sign = (bits >> 63) as u16;
+    let exp = ((bits >> 52) & 0x7ff) as i32;
+    let frac = bits & ((1 << 52) - 1);
+    // Unlike a double, the 64-bit significand has an explicit integer bit.
+    let (exp, significand) = match exp {
+        0 if frac == 0 => (0, 0),
+        0 => {
+            let shift = frac.leading_zeros() as i32;
+            (16383 - 1011 - shift, frac << shift)
+        }
+        0x7ff => (0x7fff, 1 << 63 | frac << 11),
+        _ => (exp - 1023 + 16383, 1 << 63 | frac << 11),
+    };
+
+    let mut bytes = [0; 10];
+    bytes[..8].copy_from_slice(&significand.to_le_bytes());
+    bytes[8..].copy_from_slice(&(sign << 15 | exp as u16).to_le_bytes());
+    bytes
+}
+
+/// Returns the suffix of the scalar SSE instructions operating on values of
+/// the floating type `ty`, such as `addss` and `addsd`.
+fn sse_suffix(ty: &Type) -> &'static str {
+    match ty.kind {
+        TypeKind::Float { .. } => "ss",
+        _ => "sd",
+    }
+}
+
+/// Moves the value of floating type `ty` in `xmm0` to `rax`, with a `float`
+/// zero-extended from its 32 bits like one loaded from memory.
+fn flonum_to_rax(ty: &Type, asm: &mut Vec<String>) {
+    match ty.kind {
+        TypeKind::Float { .. } => asm.push(String::from("  movd eax, xmm0")),
+        _ => asm.push(String::from("  movq rax, xmm0")),
+    }
+}
+
+/// Converts the value of floating type `ty` in `rax`, or in `st(0)` for a
+/// `long double`, to `_Bool` in `rax`. NaN compares unequal to zero, so it
+/// converts to 1.
+fn flonum_to_bool(ty: &Type, asm: &mut Vec<String>) {
+    if ty.is_long_double() {
+        asm.push(String::from("  fldz"));
+        asm.push(String::from("  fucomip st, st(1)"));
+        asm.push(String::from("  fstp st(0)"));
+    } else {
+        asm.push(String::from("  movq xmm0, rax"));
+        asm.push(String::from("  xorps xmm1, xmm1"));
+        asm.push(format!("  ucomi{} xmm0, xmm1", sse_suffix(ty)));
+    }
+    asm.push(String::from("  setne al"));
+    asm.push(String::from("  setp dl"));
+    asm.push(String::from("  or al, dl"));
+    asm.push(String::from("  movzx eax, al"));
+}
+
+/// Compares the value of type `ty` popped by `pop` with zero, setting ZF if
+/// it is zero.
+fn cmp_zero(ty: Option<&Type>, asm: &mut Vec<String>) {
+    if let Some(ty) = ty.filter(|ty| ty.is_flonum()) {
+        flonum_to_bool(ty, asm);
+    }
+    asm.push(String::from("  cmp rax, 0"));
+}
+
+/// Converts the value popped by `pop` from type `from` to type `to`, leaving
+/// it where `push` expects it.
+///
+/// SSE and the x87 only convert between floating values and signed integers,
+/// so `unsigned long` values at or above 2^63 take a separate path, branched
+/// to with labels numbered by `count`.
+fn cast(from: &Type, to: &Type, asm: &mut Vec<String>, count: &mut usize) {
+    if to.is_bool() {
+        if from.is_flonum() {
+            flonum_to_bool(from, asm);
+        } else {
+            to_bool("rax", "al", asm);
+        }
+        return;
+    }
+    if from.is_long_double() || to.is_long_double() {
+        cast_x87(from, to, asm, count);
+        return;
+    }
+
+    let is_ulong = |ty: &Type| ty.is_unsigned && ty.size() == Some(8);
+    match (from.is_flonum(), to.is_flonum()) {
+        (false, false) => extend(to, asm),
+        (false, true) => {
+            let sfx = sse_suffix(to);
+            if is_ulong(from) {
+                *count += 1;
+                let c = *count;
+                asm.push(String::from("  test rax, rax"));
+                asm.push(format!("  js .L.cvt{}", c));
+                asm.push(format!("  cvtsi2{} xmm0, rax", sfx));
+                asm.push(format!("  jmp .L.cvt_end{}", c));
+                asm.push(format!(".L.cvt{}:", c));
+                // Halve the value, keeping its lowest bit so that it still
+                // rounds the same way.
+                asm.push(String::from("  mov rdi, rax"));
+                asm.push(String::from("  and edi, 1"));
+                asm.push(String::from("  shr rax"));
+                asm.push(String::from("  or rax, rdi"));
+                asm.push(format!("  cvtsi2{} xmm0, rax", sfx));
+                asm.push(format!("  add{} xmm0, xmm0", sfx));
+                asm.push(format!(".L.cvt_end{}:", c));
+            } else {
+                asm.push(format!("  cvtsi2{} xmm0, rax", sfx));
+            }
+            flonum_to_rax(to, asm);
+        }
+        (true, false) => {
+            let sfx = sse_suffix(from);
+            asm.push(String::from("  movq xmm0, rax"));
+            if is_ulong(to) {
+                *count += 1;
+                let c = *count;
+                let two_63: u64 = match from.kind {
+                    TypeKind::Float { .. } => (2f32.powi(63)).to_bits() as u64,
+                    _ => 2f64.powi(63).to_bits(),
+                };
+                asm.push(format!("  mov rax, {}", two_63));
+                asm.push(String::from("  movq xmm1, rax"));
+                asm.push(format!("  ucomi{} xmm0, xmm1", sfx));
+                asm.push(format!("  jae .L.cvt{}", c));
+                asm.push(format!("  cvtt{}2si rax, xmm0", sfx));
+                asm.push(format!("  jmp .L.cvt_end{}", c));
+                asm.push(format!(".L.cvt{}:", c));
+                asm.push(format!("  sub{} xmm0, xmm1", sfx));
+                asm.push(format!("  cvtt{}2si rax, xmm0", sfx));
+                asm.push(String::from("  btc rax, 63"));
+                asm.push(format!(".L.cvt_end{}:", c));
+            } else {
+                asm.push(format!("  cvtt{}2si rax, xmm0", sfx));
+            }
+            extend(to, asm);
+        }
+        (true, true) => {
+            let (from_sfx, to_sfx) = (sse_suffix(from), sse_suffix(to));
+            if from_sfx != to_sfx {
+                asm.push(String::from("  movq xmm0, rax"));
+                asm.push(format!("  cvt{}2{} xmm0, xmm0", from_sfx, to_sfx));
+                flonum_to_rax(to, asm);
+            }
+        }
+    }
+}
+
+/// Converts the value of type `from` to type `to`, one of which is
+/// `long double`, going through memory at `rsp` between `rax` and `st(0)`.
+fn cast_x87(from: &Type, to: &Type, asm: &mut Vec<String>, count: &mut usize) {
+    let is_ulong = |ty: &Type| ty.is_unsigned && ty.size() == Some(8);
+    match (from.is_long_double(), to.is_long_double()) {
+        (true, true) => (),
+        (false, true) => {
+            asm.push(String::from("  push rax"));
+            match from.kind {
+                TypeKind::Float { .. } => asm.push(String::from("  fld DWORD PTR [rsp]")),
+                TypeKind::Double { .. } => asm.push(String::from("  fld QWORD PTR [rsp]")),
+                _ => {
+                    asm.push(String::from("  fild QWORD PTR [rsp]"));
+                    if is_ulong(from) {
+                        // fild reads a signed value, which is 2^64 less.
+                        *count += 1;
+                        let c = *count;
+                        asm.push(String::from("  test rax, rax"));
+                        asm.push(format!("  jns .L.cvt_end{}", c));
+                        asm.push(format!(
+                            "  mov DWORD PTR [rsp], {}",
+                            2f32.powi(64).to_bits()
+                        ));
+                        asm.push(String::from("  fadd DWORD PTR [rsp]"));
+                        asm.push(format!(".L.cvt_end{}:", c));
+                    }
+                }
+            }
+            asm.push(String::from("  add rsp, 8"));
+        }
+        (true, false) => {
+            asm.push(String::from("  sub rsp, 8"));
+            match to.kind {
+                TypeKind::Float { .. } => {
+                    asm.push(String::from("  fstp DWORD PTR [rsp]"));
+                    asm.push(String::from("  mov eax, DWORD PTR [rsp]"));
+                }
+                TypeKind::Double { .. } => {
+                    asm.push(String::from("  fstp QWORD PTR [rsp]"));
+                    asm.push(String::from("  mov rax, [rsp]"));
+                }
+                _ if is_ulong(to) => {
+                    *count += 1;
+                    let c = *count;
+                    asm.push(format!(
+                        "  mov DWORD PTR [rsp], {}",
+                        2f32.powi(63).to_bits()
+                    ));
+                    asm.push(String::from("  fld DWORD PTR [rsp]"));
+                    asm.push(String::from("  fcomip st, st(1)"));
+                    asm.push(format!("  jbe .L.cvt{}", c));
+                    asm.push(String::from("  fisttp QWORD PTR [rsp]"));
+                    asm.push(String::from("  mov rax, [rsp]"));
+                    asm.push(format!("  jmp .L.cvt_end{}", c));
+                    asm.push(format!(".L.cvt{}:", c));
+                    asm.push(String::from("  fsub DWORD PTR [rsp]"));
+                    asm.push(String::from("  fisttp QWORD PTR [rsp]"));
+                    asm.push(String::from("  mov rax, [rsp]"));
+                    asm.push(String::from("  btc rax, 63"));
+                    asm.push(format!(".L.cvt_end{}:", c));
+                }
+                _ => {
+                    // Unlike fistp, fisttp truncates whatever the rounding
+                    // mode.
+                    asm.push(String::from("  fisttp QWORD PTR [rsp]"));
+                    asm.push(String::from("  mov rax, [rsp]"));
+                    extend(to, asm);
+                }
+            }
+            asm.push(String::from("  add rsp, 8"));
+        }
+        (false, false) => unreachable!("neither type is long double"),
+    }
+}
 
 impl Tokens {
     pub(crate) fn codegen(&mut self, asm: &mut Vec<String>) {
@@ -17,7 +497,19 @@ impl Tokens {
             asm.push(format!("{}:", func.name));
 
             asm.push(String::from("  push rbp"));
-            asm.push(String::from("  mov rbp, rsp"));
+            let align = func.frame_align();
+            if align > 16 {
+                // rbp is only known to be 16-byte aligned, so a frame with
+                // more strictly aligned locals starts at the next aligned
+                // address below, which holds a pointer to the saved rbp.
+                asm.push(String::from("  mov r11, rsp"));
+                asm.push(String::from("  lea rbp, [rsp - 8]"));
+                asm.push(format!("  and rbp, -{}", align));
+                asm.push(String::from("  mov [rbp], r11"));
+                asm.push(String::from("  mov rsp, rbp"));
+            } else {
+                asm.push(String::from("  mov rbp, rsp"));
+            }
             log::debug!("stack size={:?}", func.stack_size);
             asm.push(format!("  sub rsp, {}", func.stack_size.unwrap()));
 
@@ -26,23 +518,21 @@ impl Tokens {
             func.gen_stmt(&func.body, asm, &mut count);
             asm.push(String::from("  pop rax"));
 
-            asm.push(String::from("  mov rsp, rbp"));
-            asm.push(String::from("  pop rbp"));
-            asm.push(String::from("  ret"));
+            func.epilogue(asm);
         }
     }
 
     fn emit_data(&self, asm: &mut Vec<String>) {
         for global in &self.globals {
             asm.push(String::from(".data"));
+            asm.push(format!(".align {}", global.align));
             asm.push(format!(".globl {}", global.name));
             asm.push(format!("{}:", global.name));
 
             if let Some(data) = global.init_data.as_ref() {
-                for ch in data.chars() {
-                    asm.push(format!("  .byte {}", ch as u8));
+                for byte in data {
+                    asm.push(format!("  .byte {}", byte));
                 }
-                asm.push(String::from("  .byte 0"));
             } else {
                 asm.push(format!("  .zero {}", global.ty.size().unwrap()));
             }
@@ -51,32 +541,109 @@ impl Tokens {
 }
 
 impl Function {
+    /// Returns the alignment of the frame, which is more than the 16 bytes
+    /// of the stack if a local requires it.
+    fn frame_align(&self) -> u64 {
+        self.locals.iter().map(|lvar| lvar.align).fold(16, u64::max)
+    }
+
+    /// Restores the frame of the caller and returns to it.
+    fn epilogue(&self, asm: &mut Vec<String>) {
+        if self.frame_align() > 16 {
+            asm.push(String::from("  mov rsp, [rbp]"));
+        } else {
+            asm.push(String::from("  mov rsp, rbp"));
+        }
+        asm.push(String::from("  pop rbp"));
+        asm.push(String::from("  ret"));
+    }
+
     fn gen_param(&self, asm: &mut Vec<String>) {
-        for (i, var) in self.params.iter().enumerate() {
-            let var = self.find_lvar(&var).unwrap();
-            asm.push(String::from("  mov rax, rbp"));
-            asm.push(format!("  sub rax, {}", var.offset));
-            asm.push(String::from("  push rax"));
-            asm.push(format!("  push {}", ARG_REG64[i]));
-            asm.push(String::from("  pop rdi"));
-            asm.push(String::from("  pop rax"));
-            if matches!(var.ty.size(), Some(size) if size == 1) {
-                asm.push(String::from("  mov [rax], dil"));
-            } else {
-                asm.push(String::from("  mov [rax], rdi"));
+        let (locs, _, _) = classify_args(self.params.iter().map(|param| &param.ty), 0);
+        // Stack arguments start above the saved rbp and return address.
+        let args = if self.frame_align() > 16 {
+            asm.push(String::from("  mov r10, [rbp]"));
+            "r10"
+        } else {
+            "rbp"
+        };
+        for (var, loc) in self.params.iter().zip(locs) {
+            let var = self.find_lvar(var).unwrap();
+            let offset = -(var.offset as i64);
+            let size = var.ty.size().unwrap();
+            match loc {
+                ArgLoc::Reg { mut gp, mut fp } if var.ty.is_struct_or_union() => {
+                    let eightbytes = (0..size).step_by(8);
+                    for (eightbyte, is_sse) in eightbytes.zip(eightbyte_classes(&var.ty)) {
+                        let (reg, reg8) = if is_sse {
+                            asm.push(format!("  movq r11, xmm{}", fp));
+                            fp += 1;
+                            ("r11", "r11b")
+                        } else {
+                            gp += 1;
+                            (ARG_REG64[gp - 1], ARG_REG8[gp - 1])
+                        };
+                        store_bytes(
+                            reg,
+                            reg8,
+                            "rbp",
+                            offset + eightbyte as i64,
+                            (size - eightbyte).min(8),
+                            asm,
+                        );
+                    }
+                }
+                ArgLoc::Reg { fp, .. } if var.ty.is_flonum() => {
+                    asm.push(format!(
+                        "  mov{} {}, xmm{}",
+                        sse_suffix(&var.ty),
+                        addr("rbp", offset),
+                        fp
+                    ));
+                }
+                ArgLoc::Reg { gp: reg, .. } => {
+                    if var.ty.is_bool() {
+                        bool_arg(ARG_REG8[reg], asm);
+                    }
+                    asm.push(format!(
+                        "  mov {}, {}",
+                        addr("rbp", offset),
+                        arg_reg(reg, size)
+                    ));
+                }
+                ArgLoc::Stack(stack_offset)
+                    if var.ty.is_struct_or_union() || var.ty.is_long_double() =>
+                {
+                    copy_bytes("rbp", offset, args, 16 + stack_offset as i64, size, asm);
+                }
+                ArgLoc::Stack(stack_offset) => {
+                    asm.push(format!(
+                        "  mov rax, {}",
+                        addr(args, 16 + stack_offset as i64)
+                    ));
+                    if var.ty.is_bool() {
+                        bool_arg("al", asm);
+                    }
+                    asm.push(format!("  mov {}, {}", addr("rbp", offset), rax(size)));
+                }
             }
-            asm.push(String::from("  push rdi"));
         }
     }
 
-    fn assign_lvar_offset(&mut self) -> u16 {
+    fn assign_lvar_offset(&mut self) -> u64 {
         let mut offset = 0;
         log::debug!("locals={:?}", self.locals);
         for lvar in &mut self.locals.iter_mut() {
-            offset += lvar.ty.size().unwrap();
+            // A variable-length array is allocated at run time, and only a
+            // pointer to it is kept in the frame.
+            offset += match lvar.ty.kind {
+                TypeKind::Vla { .. } => 8,
+                _ => lvar.ty.size().unwrap(),
+            };
+            offset = offset.div_ceil(lvar.align) * lvar.align;
             lvar.offset = offset;
         }
-        (offset + 16 - 1) / 16 * 16
+        offset.div_ceil(16) * 16
     }
 
     fn find_lvar(&self, var: &Var) -> Option<&Var> {
@@ -85,40 +652,220 @@ impl Function {
             .find(|lvar| lvar.name == var.name && lvar.id == var.id)
     }
 
+    /// Replaces the address in `rax` with the value of type `node.ty` stored
+    /// there, or loads it into `st(0)` for a `long double`.
+    ///
+    /// Every load and store in the source is emitted exactly once, in order.
+    /// Accesses to `volatile` objects rely on this: an optimization that
+    /// removes or merges memory accesses must leave theirs alone.
     fn load(&self, node: &Node, asm: &mut Vec<String>) {
         if let Some(ty) = &node.ty {
-            if let TypeKind::Array { .. } = ty.kind {
-                return;
-            }
-            if matches!(ty.size(), Some(size) if size == 1) {
-                asm.push(String::from("  movzx rax, BYTE PTR [rax]"));
+            // Arrays, structs, unions and functions are used through their
+            // addresses.
+            if matches!(
+                ty.kind,
+                TypeKind::Array { .. }
+                    | TypeKind::Vla { .. }
+                    | TypeKind::Struct { .. }
+                    | TypeKind::Union { .. }
+                    | TypeKind::Func { .. }
+            ) {
                 return;
             }
+
+            load_scalar(ty, asm);
+            return;
         }
 
         asm.push(String::from("  mov rax, [rax]"))
     }
 
+    /// Saves `rsp` in `vla_sp`, the hidden local of a block that allocates
+    /// variable-length arrays.
+    fn save_sp(&self, vla_sp: Option<&Var>, asm: &mut Vec<String>) {
+        if let Some(var) = vla_sp {
+            let var = self.find_lvar(var).unwrap();
+            asm.push(format!("  mov {}, rsp", addr("rbp", -(var.offset as i64))));
+        }
+    }
+
+    /// Frees the variable-length arrays allocated since `save_sp`.
+    fn restore_sp(&self, vla_sp: Option<&Var>, asm: &mut Vec<String>) {
+        if let Some(var) = vla_sp {
+            let var = self.find_lvar(var).unwrap();
+            asm.push(format!("  mov rsp, {}", addr("rbp", -(var.offset as i64))));
+        }
+    }
+
+    /// Replaces the address in `rax` with the value of the bit-field `member`
+    /// of the object there, extended to 64 bits.
+    fn load_bitfield(&self, member: &Member, asm: &mut Vec<String>) {
+        load_scalar(&member.ty, asm);
+        let shr = if member.ty.is_unsigned { "shr" } else { "sar" };
+        asm.push(format!(
+            "  shl rax, {}",
+            64 - member.bit_width - member.bit_offset
+        ));
+        asm.push(format!("  {} rax, {}", shr, 64 - member.bit_width));
+    }
+
+    /// Stores the value in `rdi` to the bit-field `member` of the object at
+    /// the address in `rax`, leaving the other bits of its storage unit
+    /// alone. Afterwards `rdi` holds the value the bit-field now reads as.
+    fn store_bitfield(&self, member: &Member, asm: &mut Vec<String>) {
+        let mask = if member.bit_width == 64 {
+            u64::MAX
+        } else {
+            (1 << member.bit_width) - 1
+        };
+        asm.push(String::from("  mov r8, rdi"));
+        asm.push(format!("  mov r11, {}", mask));
+        asm.push(String::from("  and rdi, r11"));
+        asm.push(format!("  shl rdi, {}", member.bit_offset));
+
+        asm.push(String::from("  mov r9, rax"));
+        let mut unit = member.ty.clone();
+        unit.is_unsigned = true;
+        load_scalar(&unit, asm);
+        asm.push(format!(
+            "  mov r11, {}",
+            !(mask << member.bit_offset) as i64
+        ));
+        asm.push(String::from("  and rax, r11"));
+        asm.push(String::from("  or rdi, rax"));
+        asm.push(String::from("  mov rax, r9"));
+        asm.push(format!(
+            "  mov [rax], {}",
+            arg_reg(0, member.ty.size().unwrap())
+        ));
+
+        let shr = if member.ty.is_unsigned { "shr" } else { "sar" };
+        asm.push(String::from("  mov rdi, r8"));
+        asm.push(format!("  shl rdi, {}", 64 - member.bit_width));
+        asm.push(format!("  {} rdi, {}", shr, 64 - member.bit_width));
+    }
+
+    /// Stores the value in `rdi` to the address in `rax`. Struct and union
+    /// values are addresses too, so they are copied byte by byte.
     fn store(&self, node: &Node, asm: &mut Vec<String>) {
-        if let Some(ty) = &node.ty {
-            if matches!(ty.size(), Some(size) if size == 1) {
-                asm.push(String::from("  mov [rax], dil"));
+        let size = node.ty.as_ref().and_then(|ty| ty.size()).unwrap_or(8);
+        if node.ty.as_ref().is_some_and(|ty| ty.is_struct_or_union()) {
+            copy_bytes("rax", 0, "rdi", 0, size, asm);
+            return;
+        }
+        asm.push(format!("  mov [rax], {}", arg_reg(0, size)));
+    }
+
+    /// Applies the operator of `node` to the operands of floating type `ty` in
+    /// `rax` and `rdi`, and pushes the result.
+    ///
+    /// A comparison involving NaN is unordered: ucomiss and ucomisd then set
+    /// ZF, PF and CF all at once. Only `!=` is true in that case, so PF is
+    /// checked for `==` and `!=`, and the operands of `<` and `<=` are swapped
+    /// to test CF, which `seta` and `setae` require to be clear.
+    fn gen_flonum_binary(&self, node: &Node, ty: &Type, asm: &mut Vec<String>) {
+        let sfx = sse_suffix(ty);
+        asm.push(String::from("  movq xmm0, rax"));
+        asm.push(String::from("  movq xmm1, rdi"));
+        let op = match node.kind {
+            NodeKind::Add => "add",
+            NodeKind::Sub => "sub",
+            NodeKind::Mul => "mul",
+            NodeKind::Div => "div",
+            NodeKind::Eq => {
+                asm.push(format!("  ucomi{} xmm0, xmm1", sfx));
+                asm.push(String::from("  sete al"));
+                asm.push(String::from("  setnp dl"));
+                asm.push(String::from("  and al, dl"));
+                asm.push(String::from("  movzx eax, al"));
+                asm.push(String::from("  push rax"));
                 return;
             }
-        }
+            NodeKind::Ne => {
+                asm.push(format!("  ucomi{} xmm0, xmm1", sfx));
+                asm.push(String::from("  setne al"));
+                asm.push(String::from("  setp dl"));
+                asm.push(String::from("  or al, dl"));
+                asm.push(String::from("  movzx eax, al"));
+                asm.push(String::from("  push rax"));
+                return;
+            }
+            NodeKind::Lt | NodeKind::Le => {
+                asm.push(format!("  ucomi{} xmm1, xmm0", sfx));
+                match node.kind {
+                    NodeKind::Lt => asm.push(String::from("  seta al")),
+                    _ => asm.push(String::from("  setae al")),
+                }
+                asm.push(String::from("  movzx eax, al"));
+                asm.push(String::from("  push rax"));
+                return;
+            }
+            _ => unreachable!("invalid floating-point operator"),
+        };
+        asm.push(format!("  {}{} xmm0, xmm1", op, sfx));
+        flonum_to_rax(ty, asm);
+        asm.push(String::from("  push rax"));
+    }
 
-        asm.push(String::from("  mov [rax], rdi"));
+    /// Applies the operator of `node` to the `long double` operands on the
+    /// stack, and pushes the result.
+    ///
+    /// Only the x87 forms with `st(0)` as the destination are used, since
+    /// the assembler swaps the meaning of `fsub` and `fsubr`, and of `fdiv`
+    /// and `fdivr`, for the others. As with SSE, the operands of comparisons
+    /// are swapped so that NaN makes `<` and `<=` false.
+    fn gen_x87_binary(&self, node: &Node, ty: &Type, asm: &mut Vec<String>) {
+        let op = match node.kind {
+            NodeKind::Add => "fadd",
+            NodeKind::Sub => "fsub",
+            NodeKind::Mul => "fmul",
+            NodeKind::Div => "fdiv",
+            NodeKind::Eq | NodeKind::Ne | NodeKind::Lt | NodeKind::Le => {
+                asm.push(String::from("  fld TBYTE PTR [rsp + 16]"));
+                asm.push(String::from("  fld TBYTE PTR [rsp]"));
+                asm.push(String::from("  add rsp, 32"));
+                asm.push(String::from("  fcomip st, st(1)"));
+                asm.push(String::from("  fstp st(0)"));
+                match node.kind {
+                    NodeKind::Eq => {
+                        asm.push(String::from("  sete al"));
+                        asm.push(String::from("  setnp dl"));
+                        asm.push(String::from("  and al, dl"));
+                    }
+                    NodeKind::Ne => {
+                        asm.push(String::from("  setne al"));
+                        asm.push(String::from("  setp dl"));
+                        asm.push(String::from("  or al, dl"));
+                    }
+                    NodeKind::Lt => asm.push(String::from("  seta al")),
+                    _ => asm.push(String::from("  setae al")),
+                }
+                asm.push(String::from("  movzx eax, al"));
+                asm.push(String::from("  push rax"));
+                return;
+            }
+            _ => unreachable!("invalid floating-point operator"),
+        };
+        asm.push(String::from("  fld TBYTE PTR [rsp]"));
+        asm.push(String::from("  fld TBYTE PTR [rsp + 16]"));
+        asm.push(String::from("  add rsp, 32"));
+        asm.push(format!("  {} st, st(1)", op));
+        asm.push(String::from("  fstp st(1)"));
+        push(Some(ty), asm);
     }
 
     fn gen_lval(&self, node: &Node, asm: &mut Vec<String>, count: &mut usize) {
         match &node.kind {
             NodeKind::Var(var) => {
                 if var.is_local {
-                    asm.push(String::from("  mov rax, rbp"));
-                    asm.push(format!(
-                        "  sub rax, {}",
-                        self.find_lvar(&var).unwrap().offset
-                    ));
+                    let offset = self.find_lvar(var).unwrap().offset;
+                    if var.ty.is_vla() {
+                        // The frame holds a pointer to the array.
+                        asm.push(format!("  mov rax, {}", addr("rbp", -(offset as i64))));
+                    } else {
+                        asm.push(String::from("  mov rax, rbp"));
+                        asm.push(format!("  sub rax, {}", offset));
+                    }
                 } else {
                     asm.push(format!("  lea rax, {}[rip]", var.name));
                 }
@@ -126,25 +873,30 @@ impl Function {
             }
             NodeKind::Deref => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
                 }
             }
             NodeKind::Comma => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
                 }
                 if let Some(node) = node.rhs.as_ref() {
-                    self.gen_lval(&node, asm, count);
+                    self.gen_lval(node, asm, count);
                 }
             }
             NodeKind::Member(member) => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_lval(&node, asm, count);
+                    self.gen_lval(node, asm, count);
                 }
                 asm.push(String::from("  pop rax"));
                 asm.push(format!("  add rax, {}", member.offset));
                 asm.push(String::from("  push rax"));
             }
+            // A struct or union value is already the address of the object
+            // holding it, such as the temporary receiving a returned struct.
+            _ if node.ty.as_ref().is_some_and(|ty| ty.is_struct_or_union()) => {
+                self.gen_expr(node, asm, count);
+            }
             _ => unreachable!("not lval"),
         }
     }
@@ -154,54 +906,102 @@ impl Function {
             NodeKind::Return => {
                 if let Some(node) = node.lhs.as_ref() {
                     self.gen_expr(node, asm, count);
+                    pop(node.ty.as_ref(), asm);
+                    match node.ty.as_ref() {
+                        Some(ty) if ty.is_returned_in_memory() => {
+                            let ret_ptr = self.find_lvar(self.params.front().unwrap()).unwrap();
+                            asm.push(format!(
+                                "  mov rdi, {}",
+                                addr("rbp", -(ret_ptr.offset as i64))
+                            ));
+                            copy_bytes("rdi", 0, "rax", 0, ty.size().unwrap(), asm);
+                            asm.push(String::from("  mov rax, rdi"));
+                        }
+                        Some(ty) if ty.is_long_double_struct() => {
+                            asm.push(String::from("  fld TBYTE PTR [rax]"));
+                        }
+                        Some(ty) if ty.is_struct_or_union() => {
+                            let size = ty.size().unwrap();
+                            asm.push(String::from("  mov rdi, rax"));
+                            let (mut gp, mut fp) = (0, 0);
+                            let eightbytes = (0..size).step_by(8);
+                            for (eightbyte, is_sse) in eightbytes.zip(eightbyte_classes(ty)) {
+                                let size = (size - eightbyte).min(8);
+                                if is_sse {
+                                    load_bytes("r11", "r11b", "rdi", eightbyte as i64, size, asm);
+                                    asm.push(format!("  movq xmm{}, r11", fp));
+                                    fp += 1;
+                                } else {
+                                    let (reg, reg8) = [("rax", "al"), ("rdx", "dl")][gp];
+                                    load_bytes(reg, reg8, "rdi", eightbyte as i64, size, asm);
+                                    gp += 1;
+                                }
+                            }
+                        }
+                        Some(ty) if ty.is_flonum() && !ty.is_long_double() => {
+                            asm.push(String::from("  movq xmm0, rax"));
+                        }
+                        _ => (),
+                    }
                 }
-                asm.push(String::from("  pop rax"));
-                asm.push(String::from("  mov rsp, rbp"));
-                asm.push(String::from("  pop rbp"));
-                asm.push(String::from("  ret"));
-                return;
+                self.epilogue(asm);
             }
-            NodeKind::Block { body } => {
+            NodeKind::Block { body, vla_sp } => {
+                self.save_sp(vla_sp.as_ref(), asm);
                 for node in body.iter() {
                     self.gen_stmt(node, asm, count);
                 }
-                return;
+                self.restore_sp(vla_sp.as_ref(), asm);
+            }
+            NodeKind::VlaAlloc(var) => {
+                if let Some(node) = node.lhs.as_ref() {
+                    self.gen_expr(node, asm, count);
+                }
+                let var = self.find_lvar(var).unwrap();
+                asm.push(String::from("  pop rax"));
+                asm.push(String::from("  sub rsp, rax"));
+                asm.push(String::from("  and rsp, -16"));
+                asm.push(format!("  mov {}, rsp", addr("rbp", -(var.offset as i64))));
+            }
+            NodeKind::MemZero(var) => {
+                let var = self.find_lvar(var).unwrap();
+                asm.push(format!("  mov rcx, {}", var.ty.size().unwrap()));
+                asm.push(format!("  lea rdi, {}", addr("rbp", -(var.offset as i64))));
+                asm.push(String::from("  mov al, 0"));
+                asm.push(String::from("  rep stosb"));
             }
             NodeKind::ExprStmt => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
-                    asm.push(String::from("  add rsp, 8"));
+                    self.gen_expr(node, asm, count);
+                    asm.push(format!("  add rsp, {}", slot_size(node.ty.as_ref())));
                 }
-                return;
             }
             NodeKind::If { cond, then, els } => {
                 *count += 1;
-                let c = count.clone();
-                self.gen_expr(&cond, asm, count);
-                asm.push(String::from("  pop rax"));
-                asm.push(String::from("  cmp rax, 0"));
+                let c = *count;
+                self.gen_expr(cond, asm, count);
+                pop(cond.ty.as_ref(), asm);
+                cmp_zero(cond.ty.as_ref(), asm);
                 asm.push(format!("  je .L.else{}", c));
-                self.gen_stmt(&then, asm, count);
+                self.gen_stmt(then, asm, count);
                 asm.push(format!("  jmp .L.end{}", c));
                 asm.push(format!(".L.else{}:", c));
                 if let Some(els) = els {
-                    self.gen_stmt(&els, asm, count);
+                    self.gen_stmt(els, asm, count);
                 }
                 asm.push(format!(".L.end{}:", c));
-                return;
             }
             NodeKind::While { cond, then } => {
                 *count += 1;
-                let c = count.clone();
+                let c = *count;
                 asm.push(format!(".L.begin{}:", c));
-                self.gen_expr(&cond, asm, count);
-                asm.push(String::from("  pop rax"));
-                asm.push(String::from("  cmp rax, 0"));
+                self.gen_expr(cond, asm, count);
+                pop(cond.ty.as_ref(), asm);
+                cmp_zero(cond.ty.as_ref(), asm);
                 asm.push(format!("  je .L.end{}", c));
-                self.gen_stmt(&then, asm, count);
+                self.gen_stmt(then, asm, count);
                 asm.push(format!("  jmp .L.begin{}", c));
                 asm.push(format!(".L.end{}:", c));
-                return;
             }
             NodeKind::For {
                 init,
@@ -210,101 +1010,337 @@ impl Function {
                 then,
             } => {
                 *count += 1;
-                let c = count.clone();
-                self.gen_stmt(&init, asm, count);
+                let c = *count;
+                self.gen_stmt(init, asm, count);
                 asm.push(format!(".L.begin{}:", c));
                 if let Some(cond) = cond {
-                    self.gen_expr(&cond, asm, count);
-                    asm.push(String::from("  pop rax"));
-                    asm.push(String::from("  cmp rax, 0"));
+                    self.gen_expr(cond, asm, count);
+                    pop(cond.ty.as_ref(), asm);
+                    cmp_zero(cond.ty.as_ref(), asm);
                     asm.push(format!("  je .L.end{}", c));
                 }
-                self.gen_stmt(&then, asm, count);
+                self.gen_stmt(then, asm, count);
                 if let Some(inc) = inc {
-                    self.gen_expr(&inc, asm, count);
+                    self.gen_expr(inc, asm, count);
                 }
                 asm.push(format!("  jmp .L.begin{}", c));
                 asm.push(format!(".L.end{}:", c));
-                return;
             }
             _ => (),
         }
     }
 
     pub fn gen_expr(&self, node: &Node, asm: &mut Vec<String>, count: &mut usize) {
-        asm.push(format!("  .loc 1 {}", node.token.line_number));
+        asm.push(format!(
+            "  .loc {} {}",
+            node.token.span.file_id, node.token.line_number
+        ));
         match &node.kind {
             NodeKind::Num(val) => {
-                asm.push(format!("  push {}", val));
+                asm.push(format!("  mov rax, {}", val));
+                asm.push(String::from("  push rax"));
                 return;
             }
-            NodeKind::Var { .. } | NodeKind::Member(_) => {
-                self.gen_lval(&node, asm, count);
+            NodeKind::FNum { val, x87 } => {
+                match node.ty.as_ref().map(|ty| &ty.kind) {
+                    Some(TypeKind::Float { .. }) => {
+                        asm.push(format!("  mov eax, {}", (*val as f32).to_bits()))
+                    }
+                    Some(TypeKind::LongDouble { .. }) => {
+                        let bytes = x87.unwrap_or_else(|| x87_bytes(*val));
+                        let mut high = [0; 8];
+                        high[..2].copy_from_slice(&bytes[8..]);
+                        let mut low = [0; 8];
+                        low.copy_from_slice(&bytes[..8]);
+                        asm.push(format!("  mov rax, {}", u64::from_le_bytes(high)));
+                        asm.push(String::from("  push rax"));
+                        asm.push(format!("  mov rax, {}", u64::from_le_bytes(low)));
+                    }
+                    _ => asm.push(format!("  mov rax, {}", val.to_bits())),
+                }
+                asm.push(String::from("  push rax"));
+                return;
+            }
+            NodeKind::Member(member) if member.is_bitfield => {
+                self.gen_lval(node, asm, count);
                 asm.push(String::from("  pop rax"));
-                self.load(&node, asm);
+                self.load_bitfield(member, asm);
                 asm.push(String::from("  push rax"));
                 return;
             }
+            NodeKind::Var { .. } | NodeKind::Member(_) => {
+                self.gen_lval(node, asm, count);
+                asm.push(String::from("  pop rax"));
+                self.load(node, asm);
+                push(node.ty.as_ref(), asm);
+                return;
+            }
             NodeKind::Assign => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_lval(&node, asm, count);
+                    self.gen_lval(node, asm, count);
                 }
                 if let Some(node) = node.rhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
+                }
+
+                if node.ty.as_ref().is_some_and(|ty| ty.is_long_double()) {
+                    pop(node.ty.as_ref(), asm);
+                    asm.push(String::from("  pop rax"));
+                    asm.push(String::from("  fld st(0)"));
+                    asm.push(String::from("  fstp TBYTE PTR [rax]"));
+                    push(node.ty.as_ref(), asm);
+                    return;
                 }
 
                 asm.push(String::from("  pop rdi"));
                 asm.push(String::from("  pop rax"));
-                self.store(&node, asm);
-                asm.push(String::from("  push rdi"));
+                if let Some(NodeKind::Member(member)) = node.lhs.as_ref().map(|lhs| &lhs.kind) {
+                    if member.is_bitfield {
+                        self.store_bitfield(member, asm);
+                        asm.push(String::from("  push rdi"));
+                        return;
+                    }
+                }
+                self.store(node, asm);
+                if node.ty.as_ref().is_some_and(|ty| ty.is_struct_or_union()) {
+                    asm.push(String::from("  push rax"));
+                } else {
+                    asm.push(String::from("  push rdi"));
+                }
                 return;
             }
             NodeKind::Addr => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_lval(&node, asm, count);
+                    self.gen_lval(node, asm, count);
                 }
                 return;
             }
             NodeKind::Deref => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
                 }
                 asm.push(String::from("  pop rax"));
-                self.load(&node, asm);
-                asm.push(String::from("  push rax"));
+                self.load(node, asm);
+                push(node.ty.as_ref(), asm);
                 return;
             }
-            NodeKind::StmtExpr { body } => {
+            NodeKind::StmtExpr { body, vla_sp } => {
                 let mut body = body.clone();
                 let last = body.pop().unwrap();
+                self.save_sp(vla_sp.as_ref(), asm);
                 for node in body.iter() {
-                    self.gen_stmt(&node, asm, count);
+                    self.gen_stmt(node, asm, count);
                 }
                 self.gen_expr(&last, asm, count);
+                if vla_sp.is_some() {
+                    pop(last.ty.as_ref(), asm);
+                    self.restore_sp(vla_sp.as_ref(), asm);
+                    push(last.ty.as_ref(), asm);
+                }
                 return;
             }
-            NodeKind::FuncCall { name, args } => {
-                let mut nargs = 0;
+            NodeKind::FuncCall {
+                args, ret_buffer, ..
+            } => {
+                // Calls to a function named directly are emitted as such, and
+                // anything else is called through the pointer it evaluates to.
+                let callee = node.lhs.as_ref().unwrap();
+                let direct = match &callee.kind {
+                    NodeKind::Var(var) if matches!(var.ty.kind, TypeKind::Func { .. }) => {
+                        Some(&var.name)
+                    }
+                    _ => None,
+                };
+                if direct.is_none() {
+                    self.gen_expr(callee, asm, count);
+                }
                 for arg in args {
-                    self.gen_expr(&arg, asm, count);
-                    nargs += 1;
+                    self.gen_expr(arg, asm, count);
+                }
+
+                let ret_buffer = ret_buffer.as_ref().map(|var| self.find_lvar(var).unwrap());
+                let ret_in_memory = ret_buffer.is_some_and(|var| var.ty.is_returned_in_memory());
+                let (locs, stack_size, nfp) = classify_args(
+                    args.iter().map(|arg| arg.ty.as_ref().unwrap()),
+                    ret_in_memory as usize,
+                );
+
+                // Make room for the stack arguments with rsp aligned to 16
+                // bytes, as the ABI requires at the call, and save the old rsp
+                // just above them. r10 points at the evaluated arguments, the
+                // last one first.
+                asm.push(String::from("  mov r10, rsp"));
+                asm.push(format!("  sub rsp, {}", stack_size + 8));
+                asm.push(String::from("  and rsp, -16"));
+                asm.push(format!("  mov {}, r10", addr("rsp", stack_size as i64)));
+
+                let mut slots = vec![0; args.len()];
+                let mut args_size = 0;
+                for (slot, arg) in slots.iter_mut().zip(args).rev() {
+                    *slot = args_size;
+                    args_size += slot_size(arg.ty.as_ref()) as i64;
+                }
+                for ((arg, loc), &slot) in args.iter().zip(&locs).zip(&slots) {
+                    let ty = arg.ty.as_ref().unwrap();
+                    let size = ty.size().unwrap();
+                    match *loc {
+                        ArgLoc::Stack(offset) if ty.is_struct_or_union() => {
+                            asm.push(format!("  mov rax, {}", addr("r10", slot)));
+                            copy_bytes("rsp", offset as i64, "rax", 0, size, asm);
+                        }
+                        ArgLoc::Stack(offset) => {
+                            for word in (0..slot_size(Some(ty)) as i64).step_by(8) {
+                                asm.push(format!("  mov rax, {}", addr("r10", slot + word)));
+                                asm.push(format!(
+                                    "  mov {}, rax",
+                                    addr("rsp", offset as i64 + word)
+                                ));
+                            }
+                        }
+                        ArgLoc::Reg { .. } => (),
+                    }
+                }
+                // Registers are loaded last, since copying the stack arguments
+                // clobbers rax and r11.
+                for ((arg, loc), &slot) in args.iter().zip(&locs).zip(&slots) {
+                    let slot = addr("r10", slot);
+                    let ty = arg.ty.as_ref().unwrap();
+                    match *loc {
+                        ArgLoc::Reg { mut gp, mut fp } if ty.is_struct_or_union() => {
+                            let size = ty.size().unwrap();
+                            asm.push(format!("  mov rax, {}", slot));
+                            let eightbytes = (0..size).step_by(8);
+                            for (eightbyte, is_sse) in eightbytes.zip(eightbyte_classes(ty)) {
+                                let size = (size - eightbyte).min(8);
+                                if is_sse {
+                                    load_bytes("r11", "r11b", "rax", eightbyte as i64, size, asm);
+                                    asm.push(format!("  movq xmm{}, r11", fp));
+                                    fp += 1;
+                                } else {
+                                    load_bytes(
+                                        ARG_REG64[gp],
+                                        ARG_REG8[gp],
+                                        "rax",
+                                        eightbyte as i64,
+                                        size,
+                                        asm,
+                                    );
+                                    gp += 1;
+                                }
+                            }
+                        }
+                        ArgLoc::Reg { fp, .. } if ty.is_flonum() => {
+                            asm.push(format!("  movq xmm{}, {}", fp, slot));
+                        }
+                        ArgLoc::Reg { gp, .. } => {
+                            asm.push(format!("  mov {}, {}", ARG_REG64[gp], slot));
+                        }
+                        ArgLoc::Stack(_) => (),
+                    }
+                }
+                if let Some(var) = ret_buffer.filter(|_| ret_in_memory) {
+                    asm.push(format!("  lea rdi, {}", addr("rbp", -(var.offset as i64))));
                 }
 
-                for i in (0..nargs).rev() {
-                    asm.push(format!("  pop {}", ARG_REG64[i]));
+                // A variadic callee finds the number of SSE registers used for
+                // arguments in al.
+                asm.push(format!("  mov rax, {}", nfp));
+                match direct {
+                    Some(name) => asm.push(format!("  call {}", name)),
+                    None => {
+                        asm.push(format!("  mov r11, {}", addr("r10", args_size)));
+                        asm.push(String::from("  call r11"));
+                    }
                 }
+                asm.push(format!("  mov rsp, {}", addr("rsp", stack_size as i64)));
+                asm.push(format!(
+                    "  add rsp, {}",
+                    args_size + 8 * direct.is_none() as i64
+                ));
 
-                asm.push(String::from("  mov rax, 0"));
-                asm.push(format!("  call {}", name));
-                asm.push(String::from("  push rax"));
+                match ret_buffer {
+                    Some(var) => {
+                        let offset = -(var.offset as i64);
+                        let size = var.ty.size().unwrap();
+                        if var.ty.is_long_double_struct() {
+                            asm.push(format!("  fstp TBYTE PTR {}", addr("rbp", offset)));
+                        } else if !ret_in_memory {
+                            let (mut gp, mut fp) = (0, 0);
+                            let eightbytes = (0..size).step_by(8);
+                            for (eightbyte, is_sse) in eightbytes.zip(eightbyte_classes(&var.ty)) {
+                                let offset = offset + eightbyte as i64;
+                                let size = (size - eightbyte).min(8);
+                                if is_sse {
+                                    asm.push(format!("  movq r11, xmm{}", fp));
+                                    store_bytes("r11", "r11b", "rbp", offset, size, asm);
+                                    fp += 1;
+                                } else {
+                                    let (reg, reg8) = [("rax", "al"), ("rdx", "dl")][gp];
+                                    store_bytes(reg, reg8, "rbp", offset, size, asm);
+                                    gp += 1;
+                                }
+                            }
+                        }
+                        asm.push(format!("  lea rax, {}", addr("rbp", offset)));
+                    }
+                    None => match &node.ty {
+                        // The value is already in st(0).
+                        Some(ty) if ty.is_long_double() => (),
+                        Some(ty) if ty.is_flonum() => flonum_to_rax(ty, asm),
+                        Some(ty) => extend(ty, asm),
+                        None => (),
+                    },
+                }
+                push(node.ty.as_ref(), asm);
                 return;
             }
             NodeKind::Comma => {
                 if let Some(node) = node.lhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
                 }
                 if let Some(node) = node.rhs.as_ref() {
-                    self.gen_expr(&node, asm, count);
+                    self.gen_expr(node, asm, count);
+                }
+                return;
+            }
+            // Evaluated for its side effects only, such as the left operand
+            // of a comma.
+            NodeKind::ExprStmt => {
+                if let Some(node) = node.lhs.as_ref() {
+                    self.gen_expr(node, asm, count);
+                    asm.push(format!("  add rsp, {}", slot_size(node.ty.as_ref())));
+                }
+                return;
+            }
+            // Floating values are negated by flipping their sign bit, so that
+            // `-0.0` is negative zero.
+            NodeKind::Neg => {
+                if let Some(node) = node.lhs.as_ref() {
+                    self.gen_expr(node, asm, count);
+                }
+                let ty = node.ty.as_ref().unwrap();
+                pop(Some(ty), asm);
+                match ty.kind {
+                    TypeKind::LongDouble { .. } => asm.push(String::from("  fchs")),
+                    TypeKind::Double { .. } => asm.push(String::from("  btc rax, 63")),
+                    TypeKind::Float { .. } => asm.push(String::from("  btc eax, 31")),
+                    _ => {
+                        asm.push(String::from("  neg rax"));
+                        extend(ty, asm);
+                    }
+                }
+                push(Some(ty), asm);
+                return;
+            }
+            NodeKind::Cast => {
+                if let Some(node) = node.lhs.as_ref() {
+                    self.gen_expr(node, asm, count);
+                }
+                let from = node.lhs.as_ref().and_then(|lhs| lhs.ty.as_ref());
+                if let (Some(from), Some(to)) = (from, &node.ty) {
+                    pop(Some(from), asm);
+                    cast(from, to, asm, count);
+                    push(Some(to), asm);
                 }
                 return;
             }
@@ -312,13 +1348,45 @@ impl Function {
         }
 
         if let Some(node) = node.lhs.as_ref() {
-            self.gen_expr(&node, asm, count);
+            self.gen_expr(node, asm, count);
         }
         if let Some(node) = node.rhs.as_ref() {
-            self.gen_expr(&node, asm, count);
+            self.gen_expr(node, asm, count);
+        }
+
+        // Both operands have been converted to a common type by casts.
+        let lhs_ty = node.lhs.as_ref().and_then(|lhs| lhs.ty.as_ref());
+        if let Some(ty) = lhs_ty.filter(|ty| ty.is_long_double()) {
+            self.gen_x87_binary(node, ty, asm);
+            return;
         }
+
         asm.push(String::from("  pop rdi"));
         asm.push(String::from("  pop rax"));
+        if let Some(ty) = node
+            .lhs
+            .as_ref()
+            .and_then(|lhs| lhs.ty.as_ref())
+            .filter(|ty| ty.is_flonum())
+        {
+            if let NodeKind::Add
+            | NodeKind::Sub
+            | NodeKind::Mul
+            | NodeKind::Div
+            | NodeKind::Eq
+            | NodeKind::Ne
+            | NodeKind::Lt
+            | NodeKind::Le = node.kind
+            {
+                self.gen_flonum_binary(node, ty, asm);
+                return;
+            }
+        }
+        let unsigned_cmp = node
+            .lhs
+            .as_ref()
+            .and_then(|lhs| lhs.ty.as_ref())
+            .is_some_and(|ty| ty.is_unsigned);
 
         match node.kind {
             NodeKind::Add => {
@@ -331,8 +1399,13 @@ impl Function {
                 asm.push(String::from("  imul rax, rdi"));
             }
             NodeKind::Div => {
-                asm.push(String::from("  cqo"));
-                asm.push(String::from("  idiv rdi"));
+                if node.ty.as_ref().is_some_and(|ty| ty.is_unsigned) {
+                    asm.push(String::from("  mov rdx, 0"));
+                    asm.push(String::from("  div rdi"));
+                } else {
+                    asm.push(String::from("  cqo"));
+                    asm.push(String::from("  idiv rdi"));
+                }
             }
             NodeKind::Eq => {
                 asm.push(String::from("  cmp rax, rdi"));
@@ -346,17 +1419,28 @@ impl Function {
             }
             NodeKind::Lt => {
                 asm.push(String::from("  cmp rax, rdi"));
-                asm.push(String::from("  setl al"));
+                if unsigned_cmp {
+                    asm.push(String::from("  setb al"));
+                } else {
+                    asm.push(String::from("  setl al"));
+                }
                 asm.push(String::from("  movzb rax, al"));
             }
             NodeKind::Le => {
                 asm.push(String::from("  cmp rax, rdi"));
-                asm.push(String::from("  setle al"));
+                if unsigned_cmp {
+                    asm.push(String::from("  setbe al"));
+                } else {
+                    asm.push(String::from("  setle al"));
+                }
                 asm.push(String::from("  movzb rax, al"));
             }
             _ => {}
         }
 
+        if let Some(ty) = &node.ty {
+            extend(ty, asm);
+        }
         asm.push(String::from("  push rax"));
     }
 }
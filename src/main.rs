@@ -1,6 +1,8 @@
+use std::cell::OnceCell;
 use std::collections::LinkedList;
 use std::env;
 use std::fs::File;
+use std::rc::Rc;
 
 mod codegen;
 mod parse;
@@ -12,8 +14,21 @@ enum TokenKind {
     Keyword,
     Punct,
     Ident,
-    Num(u16),
-    Str { str: String, ty: Box<Type> },
+    Num {
+        val: i64,
+        ty: Box<Type>,
+    },
+    /// A floating constant. `x87` holds a `long double` one rounded from its
+    /// digits to the x87 extended precision format.
+    FNum {
+        val: f64,
+        x87: Option<Box<[u8; 10]>>,
+        ty: Box<Type>,
+    },
+    Str {
+        str: String,
+        ty: Box<Type>,
+    },
     Eof,
 }
 
@@ -23,6 +38,9 @@ enum NodeKind {
     Sub,
     Mul,
     Div,
+    /// Arithmetic negation of `lhs`, which also flips the sign of a floating
+    /// zero.
+    Neg,
     Eq,
     Ne,
     Lt,
@@ -47,79 +65,196 @@ enum NodeKind {
     Deref,
     Addr,
     Block {
-        body: Box<Vec<Node>>,
+        body: Vec<Node>,
+        /// Hidden local saving `rsp` on entry to a block that allocates
+        /// variable-length arrays, so that leaving the block frees them.
+        vla_sp: Option<Var>,
     },
     ExprStmt,
     StmtExpr {
-        body: Box<Vec<Node>>,
+        body: Vec<Node>,
+        vla_sp: Option<Var>,
     },
+    /// A call to the function designated by `lhs`.
     FuncCall {
-        name: String,
         args: Vec<Node>,
+        /// Type of the callee, if a declaration of it is visible.
+        func_ty: Option<Type>,
+        /// Temporary that receives a returned struct or union, whose address
+        /// is the value of the call.
+        ret_buffer: Option<Var>,
     },
     Var(Var),
-    Num(u16),
+    Num(i64),
+    /// A floating constant, with the full precision of a `long double` one
+    /// in `x87`. Folding constant expressions of type `long double` other
+    /// than a negated constant only has the precision of a `double`.
+    FNum {
+        val: f64,
+        x87: Option<[u8; 10]>,
+    },
     Comma,
     Member(Member),
+    Cast,
+    /// Zero-fills the local variable, before its initializer sets the
+    /// elements and members given explicitly.
+    MemZero(Var),
+    /// Allocates the `lhs` bytes of the variable-length array `Var` on the
+    /// stack.
+    VlaAlloc(Var),
 }
 
 #[derive(Debug, Clone)]
 enum TypeKind {
-    Int {
-        size: u16,
+    Void,
+    Bool {
+        size: u64,
     },
     Char {
-        size: u16,
+        size: u64,
+    },
+    Short {
+        size: u64,
+    },
+    Int {
+        size: u64,
+    },
+    Long {
+        size: u64,
+    },
+    Enum {
+        size: u64,
+    },
+    Float {
+        size: u64,
+    },
+    Double {
+        size: u64,
+    },
+    /// The x87 80-bit extended precision type, stored in 16 bytes.
+    LongDouble {
+        size: u64,
     },
     Func {
-        params: Box<Vec<Type>>,
+        params: Vec<Type>,
         return_ty: Option<Box<Type>>,
+        is_variadic: bool,
+        /// Whether the parameters are declared. A function declared with an
+        /// empty list `()` has no prototype, and takes any arguments.
+        is_prototyped: bool,
     },
     Ptr {
-        size: u16,
+        size: u64,
         base: Box<Type>,
     },
+    /// An array of `len` elements, or of unknown length if `len` is `None`,
+    /// as in `extern int a[];`.
     Array {
-        size: u16,
-        len: u16,
+        len: Option<u64>,
         base: Box<Type>,
     },
+    /// A variable-length array. Its length `len` is evaluated where it is
+    /// declared, and its size in bytes is then kept in the hidden local
+    /// `size_var`.
+    Vla {
+        len: Box<Node>,
+        base: Box<Type>,
+        size_var: Box<Var>,
+    },
     Struct {
-        size: u16,
-        members: Vec<Member>,
+        tag: Option<String>,
+        body: Rc<OnceCell<StructBody>>,
+    },
+    Union {
+        tag: Option<String>,
+        body: Rc<OnceCell<StructBody>>,
     },
 }
 
+/// The members and size of a struct or union, set once its definition has
+/// been parsed. Every copy of the type shares it, so completing a type also
+/// completes the copies made while it was incomplete, such as the pointee of
+/// `struct node *next` inside `struct node`.
+struct StructBody {
+    members: Vec<Member>,
+    size: u64,
+}
+
 #[derive(Debug, Clone)]
 struct Member {
     ty: Type,
     name: Option<Token>,
-    offset: u16,
+    offset: u64,
+    align: u64,
+    /// For a bit-field, the value occupies `bit_width` bits starting
+    /// `bit_offset` bits into the storage unit of type `ty` at `offset`.
+    is_bitfield: bool,
+    bit_offset: u64,
+    bit_width: u64,
+}
+
+/// The initializer of an object of type `ty`: either an expression, or the
+/// initializers of its leading elements or members, the rest of which are
+/// zero. An array of unknown length gets its length from the initializer.
+#[derive(Debug)]
+struct Initializer {
+    ty: Type,
+    expr: Option<Node>,
+    children: Vec<Initializer>,
 }
 
+/// An entry in the namespace of ordinary identifiers: a variable or function,
+/// a typedef name, or an enumeration constant.
 #[derive(Debug)]
 struct VarScope {
     name: String,
-    var: Var,
+    var: Option<Var>,
+    type_def: Option<Type>,
+    enum_ty: Option<Type>,
+    enum_val: i64,
 }
 
+/// An entry in the namespace of struct and enum tags.
 #[derive(Debug)]
-struct Scope {
-    vars: LinkedList<VarScope>,
+struct TagScope {
+    name: String,
+    ty: Type,
 }
 
-impl Default for Scope {
-    fn default() -> Self {
-        Scope {
-            vars: LinkedList::new(),
-        }
-    }
+/// Storage-class specifiers and other attributes read by `declspec`.
+#[derive(Debug, Default)]
+struct VarAttr {
+    is_typedef: bool,
+    is_extern: bool,
+    /// Alignment requested with `_Alignas`.
+    align: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Scope {
+    vars: LinkedList<VarScope>,
+    tags: LinkedList<TagScope>,
+    /// Hidden local saving `rsp` once the block declares a variable-length
+    /// array.
+    vla_sp: Option<Var>,
 }
 
 #[derive(Debug, Clone)]
 struct Type {
     kind: TypeKind,
     name: Option<Token>,
+    is_unsigned: bool,
+    /// Qualifiers of the type itself, such as the `const` of `int *const`
+    /// but not that of `const int *`, which belongs to the pointee.
+    qual: Qualifiers,
+}
+
+/// Type qualifiers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Qualifiers {
+    is_const: bool,
+    is_volatile: bool,
+    is_restrict: bool,
 }
 
 #[derive(Debug)]
@@ -127,10 +262,16 @@ struct Tokens {
     locals: LinkedList<Var>,
     globals: LinkedList<Var>,
     tokens: Vec<Token>,
+    file: Rc<SourceFile>,
     scope: LinkedList<Scope>,
     index: usize,
     functions: LinkedList<Function>,
     string_literal_id: usize,
+    /// Return type of the function whose body is being parsed.
+    return_ty: Option<Type>,
+    /// Local variables for the parameters of the last function declarator
+    /// parsed, which become the parameters of its body if it is a definition.
+    param_vars: Vec<Var>,
 }
 
 #[derive(Debug)]
@@ -139,25 +280,55 @@ struct Function {
     body: Node,
     params: LinkedList<Var>,
     locals: LinkedList<Var>,
-    stack_size: Option<u16>,
+    stack_size: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 struct Var {
     id: usize,
     name: String,
-    offset: u16,
+    offset: u64,
     ty: Type,
     is_local: bool,
-    init_data: Option<String>,
+    /// Contents of a global variable with an initializer.
+    init_data: Option<Vec<u8>>,
+    /// Alignment of the variable's storage, which `_Alignas` may raise above
+    /// that of its type.
+    align: u64,
+}
+
+#[derive(Debug)]
+struct SourceFile {
+    id: usize,
+    name: String,
+    contents: String,
+}
+
+/// Byte range `start..end` of a token in the file identified by `file_id`.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    file_id: usize,
+    start: usize,
+    end: usize,
 }
 
 #[derive(Debug, Clone)]
 struct Token {
     kind: TokenKind,
     str: String,
-    loc: usize,
+    span: Span,
     line_number: usize,
+    /// Whether the token was produced by a macro expansion rather than read
+    /// directly from the source file.
+    expanded: bool,
+}
+
+/// An error found while annotating an expression with types, reported at
+/// `token`.
+#[derive(Debug)]
+struct TypeError {
+    token: Token,
+    msg: String,
 }
 
 #[derive(Debug, Clone)]
@@ -177,15 +348,6 @@ struct Cli {
     contents: Option<String>,
 }
 
-impl Node {
-    fn body(&self) -> Option<Vec<Node>> {
-        match &self.kind {
-            NodeKind::Block { body } | NodeKind::StmtExpr { body } => Some(*body.clone()),
-            _ => None,
-        }
-    }
-}
-
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     use std::io::Write;
 
@@ -196,11 +358,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         usage(0)
     }
 
-    let contents = args.contents.unwrap();
-    let chars = contents.chars();
+    let file = Rc::new(SourceFile {
+        id: 1,
+        name: args.input.unwrap_or_else(|| String::from("-")),
+        contents: args.contents.unwrap(),
+    });
     let mut asm = vec![];
 
-    let tokens = match Token::tokenize(chars.clone().collect::<String>()) {
+    let tokens = match Token::tokenize(&file) {
         Ok(tokens) => tokens,
         Err(e) => {
             eprintln!("{}", e);
@@ -208,16 +373,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let mut tokens = Tokens::new(tokens);
+    let mut tokens = Tokens::new(tokens, Rc::clone(&file));
     log::debug!("all tokens: {:?}", tokens);
     tokens.program();
 
     log::debug!("parsed tokens: {:#?}", tokens);
-    asm.push(format!(".file 1 \"{}\"", args.input.unwrap()));
+    asm.push(format!(".file {} \"{}\"", file.id, file.name));
     tokens.codegen(&mut asm);
 
-    let mut file = File::create(args.output)?;
-    file.write_all(format!("{}\n", asm.join("\n")).as_bytes())?;
+    let mut output = File::create(args.output)?;
+    output.write_all(format!("{}\n", asm.join("\n")).as_bytes())?;
     Ok(())
 }
 
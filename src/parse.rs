@@ -1,8 +1,16 @@
+use crate::codegen::x87_bytes;
 use crate::{
-    Function, Member, Node, NodeKind, Scope, Token, TokenKind, Tokens, Type, TypeKind, Var,
-    VarScope,
+    Function, Initializer, Member, Node, NodeKind, Qualifiers, Scope, SourceFile, TagScope, Token,
+    TokenKind, Tokens, Type, TypeError, TypeKind, Var, VarAttr, VarScope,
 };
 use std::collections::LinkedList;
+use std::rc::Rc;
+
+/// Keywords that begin a type name.
+const TYPE_NAMES: &[&str] = &[
+    "void", "_Bool", "char", "short", "int", "long", "signed", "unsigned", "struct", "union",
+    "enum", "typedef", "_Alignas", "const", "volatile", "restrict", "extern", "float", "double",
+];
 
 impl Token {
     fn get_ident(&self) -> Option<String> {
@@ -44,7 +52,7 @@ impl Node {
         }
     }
 
-    fn new_node_num(val: u16, token: &Token) -> Self {
+    fn new_node_num(val: i64, token: &Token) -> Self {
         Node {
             kind: NodeKind::Num(val),
             lhs: None,
@@ -54,6 +62,20 @@ impl Node {
         }
     }
 
+    fn new_node_long(val: i64, token: &Token) -> Self {
+        Node {
+            ty: Some(Type::type_long()),
+            ..Self::new_node_num(val, token)
+        }
+    }
+
+    fn new_node_ulong(val: i64, token: &Token) -> Self {
+        Node {
+            ty: Some(Type::type_ulong()),
+            ..Self::new_node_num(val, token)
+        }
+    }
+
     fn new_node_var(var: Var, ty: Type, token: &Token) -> Self {
         Node {
             kind: NodeKind::Var(var),
@@ -64,126 +86,154 @@ impl Node {
         }
     }
 
+    /// Wraps `expr` in a conversion to `ty`.
+    pub fn new_cast(expr: Node, ty: Type) -> Result<Self, TypeError> {
+        let mut expr = expr;
+        expr.add_type()?;
+        let token = expr.token.clone();
+        Ok(Node {
+            kind: NodeKind::Cast,
+            lhs: Some(Box::new(expr)),
+            rhs: None,
+            ty: Some(ty),
+            token,
+        })
+    }
+
+    /// Returns whether `eval` can compute the value of the expression.
+    fn is_const_expr(&self) -> bool {
+        let is_const =
+            |node: &Option<Box<Node>>| node.as_ref().is_some_and(|node| node.is_const_expr());
+        match self.kind {
+            NodeKind::Num(_) | NodeKind::FNum { .. } => true,
+            NodeKind::Add
+            | NodeKind::Sub
+            | NodeKind::Mul
+            | NodeKind::Div
+            | NodeKind::Eq
+            | NodeKind::Ne
+            | NodeKind::Lt
+            | NodeKind::Le
+            | NodeKind::Comma => is_const(&self.lhs) && is_const(&self.rhs),
+            NodeKind::Neg | NodeKind::Cast => is_const(&self.lhs),
+            _ => false,
+        }
+    }
+
     fn new_block(body: Vec<Node>, token: &Token) -> Self {
         Node {
-            kind: NodeKind::Block {
-                body: Box::new(body),
-            },
+            kind: NodeKind::Block { body, vla_sp: None },
             lhs: None,
             rhs: None,
             ty: None,
             token: token.clone(),
         }
     }
+}
+
+impl Tokens {
+    pub fn new(tokens: Vec<Token>, file: Rc<SourceFile>) -> Self {
+        let mut scope = LinkedList::new();
+        scope.push_front(Scope::default());
+        Tokens {
+            locals: LinkedList::new(),
+            globals: LinkedList::new(),
+            scope,
+            tokens,
+            file,
+            index: 0,
+            functions: LinkedList::new(),
+            string_literal_id: 0,
+            return_ty: None,
+            param_vars: Vec::new(),
+        }
+    }
+
+    /// Returns the size of what the pointer type `ty` points to, for scaling
+    /// pointer arithmetic. It is only known at run time for a variable-length
+    /// array.
+    fn pointee_size(&self, ty: &Type, token: &Token) -> Node {
+        match ty.base() {
+            Some(base) if base.is_void() => {
+                self.error_tok(token, "arithmetic on a pointer to void")
+            }
+            Some(base) => match base.size() {
+                Some(size) => Node::new_node_long(size as i64, token),
+                None if base.is_vla() => self.vla_size(&base, token),
+                None => self.error_tok(
+                    token,
+                    format!("arithmetic on a pointer to an incomplete type '{}'", base),
+                ),
+            },
+            None => self.error_tok(token, "invalid operands"),
+        }
+    }
 
-    fn new_add(lhs: Node, rhs: Node, token: &Token) -> Self {
+    fn new_add(&self, lhs: Node, rhs: Node, token: &Token) -> Node {
         let mut lhs = lhs;
         let mut rhs = rhs;
 
-        lhs.add_type();
-        rhs.add_type();
+        self.add_type(&mut lhs);
+        self.add_type(&mut rhs);
 
-        if let Some(lhs_ty) = &lhs.ty {
-            let lhs_ty = lhs_ty.clone();
-            if let Some(rhs_ty) = &rhs.ty {
-                if lhs_ty.is_integer() && rhs_ty.is_integer() {
-                    return Node::new_binary(NodeKind::Add, lhs, rhs, token);
-                }
+        let lhs_ty = lhs.ty.clone().unwrap();
+        let rhs_ty = rhs.ty.clone().unwrap();
 
-                if lhs_ty.is_pointer() && rhs_ty.is_pointer() {
-                    panic!("invalid operands")
-                }
+        if lhs_ty.is_numeric() && rhs_ty.is_numeric() {
+            return Node::new_binary(NodeKind::Add, lhs, rhs, token);
+        }
 
-                if !lhs_ty.is_pointer() && rhs_ty.is_pointer() {
-                    let tmp = lhs;
-                    lhs = rhs;
-                    rhs = tmp;
-                }
+        if lhs_ty.is_pointer() && rhs_ty.is_pointer() {
+            self.error_tok(token, "invalid operands");
+        }
 
-                return Node::new_binary(
-                    NodeKind::Add,
-                    lhs,
-                    Node::new_binary(
-                        NodeKind::Mul,
-                        rhs,
-                        Self::new_node_num(lhs_ty.base().unwrap().size().unwrap(), token),
-                        token,
-                    ),
-                    token,
-                );
-            }
+        if !lhs_ty.is_pointer() && rhs_ty.is_pointer() {
+            std::mem::swap(&mut lhs, &mut rhs);
+        }
+        if !rhs.ty.as_ref().unwrap().is_integer() {
+            self.error_tok(token, "invalid operands");
         }
 
-        unreachable!("invalid operands")
+        let size = self.pointee_size(lhs.ty.as_ref().unwrap(), token);
+        Node::new_binary(
+            NodeKind::Add,
+            lhs,
+            Node::new_binary(NodeKind::Mul, rhs, size, token),
+            token,
+        )
     }
 
-    fn new_sub(lhs: Node, rhs: Node, token: &Token) -> Self {
+    fn new_sub(&self, lhs: Node, rhs: Node, token: &Token) -> Node {
         let mut lhs = lhs;
         let mut rhs = rhs;
 
-        lhs.add_type();
-        rhs.add_type();
+        self.add_type(&mut lhs);
+        self.add_type(&mut rhs);
 
-        if let Some(lhs_ty) = &lhs.ty {
-            if let Some(rhs_ty) = &rhs.ty {
-                if lhs_ty.is_integer() && rhs_ty.is_integer() {
-                    return Node::new_binary(NodeKind::Sub, lhs, rhs, token);
-                }
+        let lhs_ty = lhs.ty.clone().unwrap();
+        let rhs_ty = rhs.ty.clone().unwrap();
 
-                if rhs_ty.is_integer() {
-                    match &lhs_ty.kind {
-                        TypeKind::Ptr { base, .. } | TypeKind::Array { base, .. } => {
-                            let mut rhs = Node::new_binary(
-                                NodeKind::Mul,
-                                rhs,
-                                Self::new_node_num(base.size().unwrap(), token),
-                                token,
-                            );
-                            rhs.add_type();
-                            let ty = lhs.ty.clone();
-                            let mut node = Node::new_binary(NodeKind::Sub, lhs, rhs, token);
-                            node.ty = ty;
-                            return node;
-                        }
-                        _ => (),
-                    }
-                }
-
-                if rhs_ty.is_pointer() {
-                    match &lhs_ty.clone().kind {
-                        TypeKind::Ptr { base, .. } | TypeKind::Array { base, .. } => {
-                            let mut node = Node::new_binary(NodeKind::Sub, lhs, rhs, token);
-                            node.ty = Some(Type::type_int());
-                            return Node::new_binary(
-                                NodeKind::Div,
-                                node,
-                                Self::new_node_num(base.size().unwrap(), token),
-                                token,
-                            );
-                        }
-                        _ => (),
-                    }
-                }
-            }
+        if lhs_ty.is_numeric() && rhs_ty.is_numeric() {
+            return Node::new_binary(NodeKind::Sub, lhs, rhs, token);
         }
 
-        panic!("invalid operands: lhs={:?}, rhs={:?}", lhs, rhs);
-    }
-}
+        if lhs_ty.is_pointer() && rhs_ty.is_integer() {
+            let size = self.pointee_size(&lhs_ty, token);
+            let mut rhs = Node::new_binary(NodeKind::Mul, rhs, size, token);
+            self.add_type(&mut rhs);
+            let mut node = Node::new_binary(NodeKind::Sub, lhs, rhs, token);
+            node.ty = Some(lhs_ty);
+            return node;
+        }
 
-impl Tokens {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        let mut scope = LinkedList::new();
-        scope.push_front(Scope::default());
-        Tokens {
-            locals: LinkedList::new(),
-            globals: LinkedList::new(),
-            scope,
-            tokens,
-            index: 0,
-            functions: LinkedList::new(),
-            string_literal_id: 0,
+        if lhs_ty.is_pointer() && rhs_ty.is_pointer() {
+            let size = self.pointee_size(&lhs_ty, token);
+            let mut node = Node::new_binary(NodeKind::Sub, lhs, rhs, token);
+            node.ty = Some(Type::type_long());
+            return Node::new_binary(NodeKind::Div, node, size, token);
         }
+
+        self.error_tok(token, "invalid operands")
     }
 
     fn next(&mut self) -> Option<&Token> {
@@ -195,15 +245,49 @@ impl Tokens {
         self.tokens.get(self.index + 1)
     }
 
+    /// Looks up `name` in the namespace of ordinary identifiers, innermost
+    /// scope first.
+    fn find_scope(&self, name: &str) -> Option<&VarScope> {
+        self.scope
+            .iter()
+            .flat_map(|scope| scope.vars.iter())
+            .find(|sc| sc.name == name)
+    }
+
+    /// Looks up the struct or enum tag `name`, innermost scope first.
+    fn find_tag(&self, name: &str) -> Option<&Type> {
+        self.scope
+            .iter()
+            .flat_map(|scope| scope.tags.iter())
+            .find(|sc| sc.name == name)
+            .map(|sc| &sc.ty)
+    }
+
+    fn find_tag_in_current_scope(&self, name: &str) -> Option<&Type> {
+        self.scope
+            .front()
+            .and_then(|scope| scope.tags.iter().find(|sc| sc.name == name))
+            .map(|sc| &sc.ty)
+    }
+
+    fn push_tag_scope(&mut self, name: String, ty: Type) {
+        let scope = self.scope.front_mut().unwrap();
+        scope.tags.push_front(TagScope { name, ty });
+    }
+
     fn find_var(&self) -> Option<&Var> {
-        for scope in self.scope.iter().rev() {
-            for var in scope.vars.iter() {
-                if var.name.len() == self.token().str.len() && var.name == self.token().str {
-                    return Some(&var.var);
-                }
-            }
+        self.find_scope(&self.token().str)
+            .and_then(|sc| sc.var.as_ref())
+    }
+
+    /// Returns the type named by `token` if it is a typedef name.
+    fn find_typedef(&self, token: &Token) -> Option<Type> {
+        match token.kind {
+            TokenKind::Ident => self
+                .find_scope(&token.str)
+                .and_then(|sc| sc.type_def.clone()),
+            _ => None,
         }
-        None
     }
 
     fn enter_scope(&mut self) {
@@ -232,47 +316,52 @@ impl Tokens {
     fn assign(&mut self) -> Node {
         let mut node = self.equality();
         if self.consume("=") {
-            node = Node::new_binary(NodeKind::Assign, node, self.assign(), self.token());
+            let mut rhs = self.assign();
+            self.add_type(&mut node);
+            self.add_type(&mut rhs);
+            self.check_pointer_qualifiers(
+                &rhs.token,
+                node.ty.as_ref().unwrap(),
+                rhs.ty.as_ref().unwrap(),
+                "assignment",
+            );
+            node = Node::new_binary(NodeKind::Assign, node, rhs, self.token());
         }
         node
     }
 
-    fn global_variable(&mut self) {
-        let ty = self.declspec();
-        let mut first = true;
-
-        while !self.consume(';') {
-            if !first {
-                self.expect(',');
+    /// Declares the global variables of a declaration whose first declarator
+    /// has already been read as `ty`.
+    fn global_variable(&mut self, basety: Type, ty: Type, attr: &VarAttr) {
+        let mut ty = ty;
+        loop {
+            let name = self.get_name(&ty);
+            if self.find_function(&name).is_some() {
+                self.error_tok(
+                    ty.name.as_ref().unwrap(),
+                    format!("'{}' redeclared as different kind of symbol", name),
+                );
+            }
+            if attr.is_extern && !self.equal('=') {
+                self.declare_gvar(ty);
+            } else {
+                let mut init_data = None;
+                if self.consume('=') {
+                    let init = self.initializer(&ty);
+                    init_data = Some(self.gvar_data(&init));
+                    ty = init.ty;
+                }
+                self.check_object_type(&ty);
+                let align = self.var_align(&ty, attr);
+                self.add_gvar(name, ty, init_data, align);
             }
-            first = false;
-
-            let ty = self.declarator(ty.clone());
-            let gvar = self.add_gvar(
-                ty.clone().name.unwrap().get_ident().unwrap(),
-                ty.clone(),
-                None,
-            );
-            Node::new_node_var(gvar, ty, self.token());
-        }
-    }
 
-    pub fn is_function(&mut self) -> bool {
-        if self.equal(';') {
-            return false;
+            if self.consume(';') {
+                return;
+            }
+            self.expect(',');
+            ty = self.declarator(basety.clone());
         }
-        let mut tokens = Self {
-            tokens: self.tokens.clone(),
-            locals: LinkedList::new(),
-            globals: LinkedList::new(),
-            scope: LinkedList::new(),
-            index: self.index,
-            functions: LinkedList::new(),
-            string_literal_id: 0,
-        };
-        let ty = tokens.declspec();
-        let ty = tokens.declarator(ty);
-        matches!(ty.kind, TypeKind::Func { .. })
     }
 
     pub fn program(&mut self) {
@@ -282,27 +371,108 @@ impl Tokens {
                 break;
             }
 
-            if self.is_function() {
-                let function = self.function();
-                self.functions.push_back(function);
+            // The parameters of a function definition become its first locals
+            // as its declarator is read.
+            self.locals = LinkedList::new();
+
+            let mut attr = VarAttr::default();
+            let basety = self.declspec(Some(&mut attr));
+            if attr.is_typedef {
+                self.parse_typedef(basety);
                 continue;
             }
 
-            self.global_variable();
+            if self.consume(';') {
+                continue;
+            }
+
+            let ty = self.declarator(basety.clone());
+            if let TypeKind::Func { .. } = ty.kind {
+                if let Some(function) = self.function(ty) {
+                    self.functions.push_back(function);
+                }
+                continue;
+            }
+
+            self.global_variable(basety, ty, &attr);
         }
         log::debug!("functions={:?}", self.functions);
     }
 
-    fn push_scope(&mut self, name: String, var: Var) -> Option<&VarScope> {
-        let sc = VarScope { name, var };
-        if let Some(scope) = self.scope.front_mut() {
-            scope.vars.push_front(sc);
-            return scope.vars.front();
+    fn push_scope(&mut self, name: String) -> &mut VarScope {
+        let sc = VarScope {
+            name,
+            var: None,
+            type_def: None,
+            enum_ty: None,
+            enum_val: 0,
+        };
+        let scope = self.scope.front_mut().unwrap();
+        scope.vars.push_front(sc);
+        scope.vars.front_mut().unwrap()
+    }
+
+    /// Parses the declarators of a typedef and enters their names into the
+    /// current scope.
+    fn parse_typedef(&mut self, basety: Type) {
+        let mut first = true;
+
+        while !self.consume(';') {
+            if !first {
+                self.expect(',');
+            }
+            first = false;
+
+            let ty = self.declarator(basety.clone());
+            let name = self.get_name(&ty);
+            self.push_scope(name).type_def = Some(Type { name: None, ..ty });
+        }
+    }
+
+    /// Returns the declaration of the function `name` in the current scope.
+    fn find_function(&self, name: &str) -> Option<&Var> {
+        self.scope
+            .front()
+            .and_then(|scope| scope.vars.iter().find(|sc| sc.name == name))
+            .and_then(|sc| sc.var.as_ref())
+            .filter(|var| matches!(var.ty.kind, TypeKind::Func { .. }))
+    }
+
+    /// Declares the function `ty` in the current scope, diagnosing a conflict
+    /// with an earlier declaration of the same name.
+    fn declare_function(&mut self, ty: &Type) {
+        let name = self.get_name(ty);
+        let token = ty.name.as_ref().unwrap();
+        if let Some(sc) = self
+            .scope
+            .front()
+            .and_then(|scope| scope.vars.iter().find(|sc| sc.name == name))
+        {
+            let prev = match &sc.var {
+                Some(var) if matches!(var.ty.kind, TypeKind::Func { .. }) => var,
+                _ => self.error_tok(
+                    token,
+                    format!("'{}' redeclared as different kind of symbol", name),
+                ),
+            };
+            if !prev.ty.is_compatible(ty) {
+                self.error_tok(token, format!("conflicting types for '{}'", name));
+            }
         }
-        None
+
+        let var = Var {
+            id: 0,
+            name: name.clone(),
+            offset: 0,
+            ty: ty.clone(),
+            is_local: false,
+            init_data: None,
+            align: 1,
+        };
+        self.push_scope(name).var = Some(var);
     }
 
-    fn add_lvar(&mut self, name: String, ty: Type) -> Var {
+    fn add_lvar(&mut self, name: String, ty: Type, align: u64) -> Var {
         let lvar = Var {
             id: self.locals.front().map_or(0, |lvar| lvar.id + 1),
             name: name.clone(),
@@ -310,13 +480,14 @@ impl Tokens {
             ty,
             is_local: true,
             init_data: None,
+            align,
         };
         self.locals.push_front(lvar.clone());
-        self.push_scope(name, lvar.clone());
+        self.push_scope(name).var = Some(lvar.clone());
         lvar
     }
 
-    fn add_gvar(&mut self, name: String, ty: Type, init_data: Option<String>) -> Var {
+    fn add_gvar(&mut self, name: String, ty: Type, init_data: Option<Vec<u8>>, align: u64) -> Var {
         let gvar = Var {
             id: 0,
             name: name.clone(),
@@ -324,49 +495,290 @@ impl Tokens {
             ty,
             is_local: false,
             init_data,
+            align,
         };
         self.globals.push_front(gvar.clone());
-        self.push_scope(name, gvar.clone());
+        self.push_scope(name).var = Some(gvar.clone());
         gvar
     }
 
-    fn new_string_literal(&mut self, ty: Type, init_data: String) -> Var {
+    fn new_string_literal(&mut self, ty: Type, str: &str) -> Var {
         let name = format!(".L..{}", self.string_literal_id);
         self.string_literal_id += 1;
-        self.add_gvar(name, ty, Some(init_data))
+        let align = ty.align();
+        let mut init_data = vec![0; ty.size().unwrap() as usize];
+        for (byte, ch) in init_data.iter_mut().zip(str.chars()) {
+            *byte = ch as u8;
+        }
+        self.add_gvar(name, ty, Some(init_data), align)
     }
 
-    fn get_number(&self) -> u16 {
-        if let TokenKind::Num(val) = self.token().kind {
-            return val;
+    /// Declares the global variable `ty` defined in another translation unit,
+    /// as in `extern int x;`.
+    fn declare_gvar(&mut self, ty: Type) {
+        if ty.is_void() {
+            self.error_tok(ty.name.as_ref().unwrap(), "variable declared void");
         }
-        unreachable!("expected a number: {:?}", self.token());
+        let name = self.get_name(&ty);
+        let var = Var {
+            id: 0,
+            name: name.clone(),
+            offset: 0,
+            ty,
+            is_local: false,
+            init_data: None,
+            align: 1,
+        };
+        self.push_scope(name).var = Some(var);
     }
 
-    fn declspec(&mut self) -> Type {
-        if self.consume("char") {
-            return Type::type_char();
+    /// Returns the alignment of a variable of type `ty`, taking `_Alignas`
+    /// into account.
+    fn var_align(&self, ty: &Type, attr: &VarAttr) -> u64 {
+        match attr.align {
+            Some(align) if align < ty.align() => self.error_tok(
+                ty.name.as_ref().unwrap_or(self.token()),
+                format!(
+                    "_Alignas cannot reduce the alignment of '{}'",
+                    self.get_name(ty)
+                ),
+            ),
+            Some(align) => align,
+            None => ty.align(),
         }
+    }
 
-        if self.consume("int") {
-            return Type::type_int();
+    /// declspec = ("void" | "_Bool" | "char" | "short" | "int" | "long"
+    ///             | "signed" | "unsigned" | "typedef" | "struct" struct-union-decl
+    ///             | "union" struct-union-decl | enum-specifier | typedef-name
+    ///             | "_Alignas" "(" (type-name | const-expr) ")")+
+    ///
+    /// The order of type specifiers does not matter, so each keyword is
+    /// counted and the total is mapped to a type. For example,
+    /// `long int unsigned` is the same as `unsigned long`.
+    ///
+    /// `attr` receives storage-class specifiers; it is `None` in contexts where
+    /// they are not allowed.
+    fn declspec(&mut self, mut attr: Option<&mut VarAttr>) -> Type {
+        const VOID: u32 = 1;
+        const BOOL: u32 = 1 << 2;
+        const CHAR: u32 = 1 << 4;
+        const SHORT: u32 = 1 << 6;
+        const INT: u32 = 1 << 8;
+        const LONG: u32 = 1 << 10;
+        const FLOAT: u32 = 1 << 12;
+        const DOUBLE: u32 = 1 << 14;
+        const OTHER: u32 = 1 << 16;
+        const SIGNED: u32 = 1 << 17;
+        const UNSIGNED: u32 = 1 << 18;
+
+        if !self.is_type_name() {
+            self.error_token("typename expected");
         }
 
-        if self.consume("struct") {
-            return self.struct_decl();
+        let mut ty = Type::type_int();
+        let mut counter = 0;
+        let start = self.token().clone();
+        let mut qual = Qualifiers::default();
+        while self.is_type_name() {
+            if self.consume_qualifier(&mut qual) {
+                continue;
+            }
+
+            if self.equal("typedef") || self.equal("extern") {
+                match attr.as_mut() {
+                    Some(attr) => {
+                        if attr.is_typedef || attr.is_extern {
+                            self.error_token("multiple storage classes in declaration specifiers");
+                        }
+                        attr.is_typedef = self.equal("typedef");
+                        attr.is_extern = self.equal("extern");
+                    }
+                    None => {
+                        self.error_token("storage class specifier is not allowed in this context")
+                    }
+                }
+                self.next();
+                continue;
+            }
+
+            if self.equal("_Alignas") {
+                let attr = match attr.as_mut() {
+                    Some(attr) => attr,
+                    None => self.error_token("_Alignas is not allowed in this context"),
+                };
+                self.next();
+                self.expect('(');
+                let token = self.token().clone();
+                let align = if self.is_type_name() {
+                    self.typename().align() as i64
+                } else {
+                    self.const_expr()
+                };
+                if align <= 0 || align & (align - 1) != 0 {
+                    self.error_tok(&token, "requested alignment is not a positive power of 2");
+                }
+                attr.align = Some(align as u64);
+                self.expect(')');
+                continue;
+            }
+
+            if let Some(type_def) = self.find_typedef(self.token()) {
+                if counter > 0 {
+                    break;
+                }
+                self.next();
+                ty = type_def;
+                counter += OTHER;
+                continue;
+            }
+
+            if self.consume("struct") {
+                if counter > 0 {
+                    self.error_token("invalid type");
+                }
+                ty = self.struct_union_decl(false);
+                counter += OTHER;
+                continue;
+            }
+
+            if self.consume("union") {
+                if counter > 0 {
+                    self.error_token("invalid type");
+                }
+                ty = self.struct_union_decl(true);
+                counter += OTHER;
+                continue;
+            }
+
+            if self.consume("enum") {
+                if counter > 0 {
+                    self.error_token("invalid type");
+                }
+                ty = self.enum_specifier();
+                counter += OTHER;
+                continue;
+            }
+
+            if self.consume("void") {
+                counter += VOID;
+            } else if self.consume("_Bool") {
+                counter += BOOL;
+            } else if self.consume("char") {
+                counter += CHAR;
+            } else if self.consume("short") {
+                counter += SHORT;
+            } else if self.consume("int") {
+                counter += INT;
+            } else if self.consume("long") {
+                counter += LONG;
+            } else if self.consume("float") {
+                counter += FLOAT;
+            } else if self.consume("double") {
+                counter += DOUBLE;
+            } else if self.consume("signed") {
+                counter |= SIGNED;
+            } else if self.consume("unsigned") {
+                counter |= UNSIGNED;
+            }
+
+            ty = if counter == VOID {
+                Type::type_void()
+            } else if counter == BOOL {
+                Type::type_bool()
+            } else if [CHAR, SIGNED + CHAR].contains(&counter) {
+                Type::type_char()
+            } else if counter == UNSIGNED + CHAR {
+                Type::type_uchar()
+            } else if [SHORT, SHORT + INT, SIGNED + SHORT, SIGNED + SHORT + INT].contains(&counter)
+            {
+                Type::type_short()
+            } else if [UNSIGNED + SHORT, UNSIGNED + SHORT + INT].contains(&counter) {
+                Type::type_ushort()
+            } else if [INT, SIGNED, SIGNED + INT].contains(&counter) {
+                Type::type_int()
+            } else if [UNSIGNED, UNSIGNED + INT].contains(&counter) {
+                Type::type_uint()
+            } else if [
+                LONG,
+                LONG + INT,
+                LONG + LONG,
+                LONG + LONG + INT,
+                SIGNED + LONG,
+                SIGNED + LONG + INT,
+                SIGNED + LONG + LONG,
+                SIGNED + LONG + LONG + INT,
+            ]
+            .contains(&counter)
+            {
+                Type::type_long()
+            } else if [
+                UNSIGNED + LONG,
+                UNSIGNED + LONG + INT,
+                UNSIGNED + LONG + LONG,
+                UNSIGNED + LONG + LONG + INT,
+            ]
+            .contains(&counter)
+            {
+                Type::type_ulong()
+            } else if counter == FLOAT {
+                Type::type_float()
+            } else if counter == DOUBLE {
+                Type::type_double()
+            } else if counter == LONG + DOUBLE {
+                Type::type_ldouble()
+            } else {
+                self.error_token("invalid type");
+            };
         }
 
-        self.error_token("typename expected");
-        unreachable!()
+        if qual.is_restrict && !matches!(ty.kind, TypeKind::Ptr { .. }) {
+            self.error_tok(&start, "invalid use of 'restrict'");
+        }
+        ty.qual.is_const |= qual.is_const;
+        ty.qual.is_volatile |= qual.is_volatile;
+        ty.qual.is_restrict |= qual.is_restrict;
+        ty
+    }
+
+    /// Adds the qualifier at the current token to `qual`, returning whether
+    /// there was one.
+    fn consume_qualifier(&mut self, qual: &mut Qualifiers) -> bool {
+        if self.consume("const") {
+            qual.is_const = true;
+        } else if self.consume("volatile") {
+            qual.is_volatile = true;
+        } else if self.consume("restrict") {
+            qual.is_restrict = true;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// pointers = ("*" ("const" | "volatile" | "restrict")*)*
+    fn pointers(&mut self, ty: Type) -> Type {
+        let mut ty = ty;
+        while self.consume('*') {
+            ty = ty.pointer_to();
+            let mut qual = Qualifiers::default();
+            while self.consume_qualifier(&mut qual) {}
+            ty.qual = qual;
+        }
+        ty
     }
 
-    fn struct_members(&mut self) -> Type {
+    /// Parses member declarations up to the closing `}`. The offsets of the
+    /// returned members are left for the caller to assign.
+    fn struct_members(&mut self) -> Vec<Member> {
         let mut members = Vec::new();
-        let token = self.token().clone();
 
-        let mut offset = 0;
         while !self.consume('}') {
-            let basety = self.declspec();
+            let mut attr = VarAttr::default();
+            let basety = self.declspec(Some(&mut attr));
+            if attr.is_typedef || attr.is_extern {
+                self.error_token("storage class specifier is not allowed in this context");
+            }
 
             let mut i = 0;
             log::debug!("struct members={:?} i={}", basety, i);
@@ -378,112 +790,832 @@ impl Tokens {
                 i += 1;
 
                 let ty = self.declarator(basety.clone());
-                members.push(Member {
+                if ty.is_vla() {
+                    self.error_tok(
+                        ty.name.as_ref().unwrap_or(self.token()),
+                        "a member of a structure or union cannot have a variably modified type",
+                    );
+                }
+                // The last member of a struct may be an array of unknown
+                // length, which struct_union_decl checks.
+                if !matches!(ty.kind, TypeKind::Array { len: None, .. }) {
+                    self.check_object_type(&ty);
+                }
+                let mut member = Member {
                     ty: ty.clone(),
                     name: ty.name.clone(),
-                    offset,
-                });
-                offset += ty.size().unwrap();
+                    offset: 0,
+                    align: self.var_align(&ty, &attr),
+                    is_bitfield: false,
+                    bit_offset: 0,
+                    bit_width: 0,
+                };
+                if self.consume(':') {
+                    member.bit_width = self.bitfield_width(&ty);
+                    member.is_bitfield = true;
+                    // Unnamed bit-fields do not affect the alignment of the
+                    // struct.
+                    if ty.name.is_none() {
+                        member.align = 1;
+                    }
+                }
+                members.push(member);
             }
         }
 
-        Type::type_struct(members, offset, token)
+        members
     }
 
-    fn struct_decl(&mut self) -> Type {
+    /// Rejects applying `operator` to a bit-field, which has no size of its
+    /// own.
+    fn check_not_bitfield(&self, node: &Node, operator: &str) {
+        if let NodeKind::Member(member) = &node.kind {
+            if member.is_bitfield {
+                self.error_tok(
+                    &node.token,
+                    format!("'{}' applied to a bit-field", operator),
+                );
+            }
+        }
+    }
+
+    /// Reads the width after the `:` of a bit-field of type `ty`.
+    fn bitfield_width(&mut self, ty: &Type) -> u64 {
+        let token = self.token().clone();
+        let name = match &ty.name {
+            Some(name) => name.str.clone(),
+            None => String::from("<anonymous>"),
+        };
+        if !ty.is_integer() {
+            self.error_tok(
+                ty.name.as_ref().unwrap_or(&token),
+                format!("bit-field '{}' has invalid type", name),
+            );
+        }
+        let width = self.const_expr();
+        let max = match ty.kind {
+            TypeKind::Bool { .. } => 1,
+            _ => ty.size().unwrap() * 8,
+        };
+        if width < 0 {
+            self.error_tok(&token, format!("negative width in bit-field '{}'", name));
+        }
+        if width as u64 > max {
+            self.error_tok(&token, format!("width of '{}' exceeds its type", name));
+        }
+        if width == 0 && ty.name.is_some() {
+            self.error_tok(&token, format!("zero width for bit-field '{}'", name));
+        }
+        width as u64
+    }
+
+    /// enum-specifier = ident? "{" enum-list? "}"
+    ///                | ident ("{" enum-list? "}")?
+    ///
+    /// enum-list = ident ("=" const-expr)? ("," ident ("=" const-expr)?)* ","?
+    fn enum_specifier(&mut self) -> Type {
+        let ty = Type::type_enum();
+
+        let tag = match self.token().kind {
+            TokenKind::Ident => {
+                let tag = self.token().clone();
+                self.next();
+                Some(tag)
+            }
+            _ => None,
+        };
+
+        if let Some(tag) = &tag {
+            if !self.equal('{') {
+                return match self.find_tag(&tag.str) {
+                    Some(ty) if matches!(ty.kind, TypeKind::Enum { .. }) => ty.clone(),
+                    Some(_) => self.error_tok(tag, "not an enum tag"),
+                    None => self.error_tok(tag, "unknown enum type"),
+                };
+            }
+            match self.find_tag_in_current_scope(&tag.str) {
+                Some(ty) if matches!(ty.kind, TypeKind::Enum { .. }) => {
+                    self.error_tok(tag, format!("redefinition of 'enum {}'", tag.str))
+                }
+                Some(_) => {
+                    self.error_tok(tag, format!("'{}' defined as wrong kind of tag", tag.str))
+                }
+                None => (),
+            }
+        }
+
         self.expect('{');
-        self.struct_members()
+        let mut val = 0;
+        let mut first = true;
+        while !self.consume('}') {
+            if !first {
+                self.expect(',');
+                if self.consume('}') {
+                    break;
+                }
+            }
+            first = false;
+
+            let name = match self.token().get_ident() {
+                Some(name) => name,
+                None => self.error_token("expected an identifier"),
+            };
+            self.next();
+            if self.consume('=') {
+                val = self.const_expr();
+            }
+
+            let sc = self.push_scope(name);
+            sc.enum_ty = Some(ty.clone());
+            sc.enum_val = val;
+            val += 1;
+        }
+
+        if let Some(tag) = tag {
+            self.push_tag_scope(tag.str, ty.clone());
+        }
+        ty
+    }
+
+    /// struct-union-decl = ident? ("{" struct-members)?
+    ///
+    /// A tag without a body refers to the visible declaration of the tag, or
+    /// declares a new incomplete type if there is none. `struct tag;` on its
+    /// own always declares the tag in the current scope.
+    fn struct_union_decl(&mut self, is_union: bool) -> Type {
+        let new_type = |tag: Option<String>| {
+            if is_union {
+                Type::type_union(tag)
+            } else {
+                Type::type_struct(tag)
+            }
+        };
+
+        let tag = match self.token().kind {
+            TokenKind::Ident => {
+                let tag = self.token().clone();
+                self.next();
+                Some(tag)
+            }
+            _ => None,
+        };
+
+        if let Some(tag) = &tag {
+            if !self.equal('{') {
+                let found = if self.equal(';') {
+                    self.find_tag_in_current_scope(&tag.str)
+                } else {
+                    self.find_tag(&tag.str)
+                };
+                if let Some(ty) = found {
+                    self.check_tag_kind(tag, ty, is_union);
+                    return ty.clone();
+                }
+                let ty = new_type(Some(tag.str.clone()));
+                self.push_tag_scope(tag.str.clone(), ty.clone());
+                return ty;
+            }
+        }
+
+        self.expect('{');
+        let ty = match &tag {
+            Some(tag) => match self.find_tag_in_current_scope(&tag.str) {
+                Some(ty) if ty.is_incomplete() => {
+                    self.check_tag_kind(tag, ty, is_union);
+                    ty.clone()
+                }
+                Some(ty) => self.error_tok(tag, format!("redefinition of '{}'", ty)),
+                None => {
+                    let ty = new_type(Some(tag.str.clone()));
+                    self.push_tag_scope(tag.str.clone(), ty.clone());
+                    ty
+                }
+            },
+            None => new_type(None),
+        };
+
+        // Members are laid out as in the System V ABI: each one is placed at
+        // the next offset that is a multiple of its alignment, except that
+        // every member of a union starts at offset 0. The size is rounded up
+        // to the alignment of the most strictly aligned member, so that
+        // elements of an array of this type stay aligned.
+        //
+        // Bit-fields are packed into the bits that follow, moving on to the
+        // next storage unit of their type only if they would straddle one.
+        // A zero-width bit-field ends the current storage unit.
+        //
+        // A flexible array member, an array of unknown length at the end of a
+        // struct, takes up no space.
+        let mut members = self.struct_members();
+        self.check_flexible_array_member(&members, is_union);
+        let align = members.iter().map(|member| member.align).max().unwrap_or(1);
+        let mut bits = 0;
+        for member in members.iter_mut() {
+            let size = member.ty.size().unwrap_or(0);
+            if is_union {
+                let member_bits = if member.is_bitfield {
+                    member.bit_width.div_ceil(8) * 8
+                } else {
+                    size * 8
+                };
+                bits = bits.max(member_bits);
+            } else if member.is_bitfield {
+                let unit = size * 8;
+                if member.bit_width == 0 || bits / unit != (bits + member.bit_width - 1) / unit {
+                    bits = bits.div_ceil(unit) * unit;
+                }
+                member.offset = bits / unit * size;
+                member.bit_offset = bits - member.offset * 8;
+                bits += member.bit_width;
+            } else {
+                let unit = member.align * 8;
+                bits = bits.div_ceil(unit) * unit;
+                member.offset = bits / 8;
+                bits += size * 8;
+            }
+        }
+        let size = bits.div_ceil(8);
+        ty.complete(members, size.div_ceil(align) * align);
+        ty
+    }
+
+    fn check_flexible_array_member(&self, members: &[Member], is_union: bool) {
+        for (i, member) in members.iter().enumerate() {
+            if !matches!(member.ty.kind, TypeKind::Array { len: None, .. }) {
+                continue;
+            }
+            let token = member.name.as_ref().unwrap_or(self.token());
+            if is_union {
+                self.error_tok(token, "flexible array member in union");
+            }
+            if i + 1 != members.len() {
+                self.error_tok(token, "flexible array member not at end of struct");
+            }
+            if !members[..i].iter().any(|member| member.name.is_some()) {
+                self.error_tok(
+                    token,
+                    "flexible array member in a struct with no named members",
+                );
+            }
+        }
+    }
+
+    /// Rejects using a tag with a different kind of type than it was
+    /// declared with, as in `struct s; union s *p;`.
+    fn check_tag_kind(&self, tag: &Token, ty: &Type, is_union: bool) {
+        let matches = match ty.kind {
+            TypeKind::Struct { .. } => !is_union,
+            TypeKind::Union { .. } => is_union,
+            _ => false,
+        };
+        if !matches {
+            self.error_tok(tag, format!("'{}' defined as wrong kind of tag", tag.str));
+        }
     }
 
     fn struct_ref(&self, lhs: &mut Node) -> Node {
-        lhs.add_type();
+        self.add_type(lhs);
         match &lhs.ty {
-            Some(ty) if matches!(ty.kind, TypeKind::Struct { .. }) => (),
-            _ => self.error_token("not a struct`"),
+            Some(ty) if ty.is_incomplete() => self.error_tok(
+                &lhs.token,
+                format!("invalid use of incomplete type '{}'", ty),
+            ),
+            Some(ty) if ty.is_struct_or_union() => (),
+            _ => self.error_token("not a struct nor a union"),
+        }
+
+        Node::new_unary(
+            NodeKind::Member(self.get_struct_member(lhs.ty.clone().unwrap())),
+            lhs.clone(),
+            self.token(),
+        )
+    }
+
+    fn get_struct_member(&self, ty: Type) -> Member {
+        let members = match ty.members() {
+            Some(members) => members,
+            None => self.error_token(format!("invalid use of incomplete type '{}'", ty)),
+        };
+        log::debug!(
+            "struct members={:?} token name={} token span={:?}",
+            members,
+            self.token().str,
+            self.token().span
+        );
+        let name = self.token().clone();
+        match members.iter().find(|member| {
+            member.name.as_ref().is_some_and(|member_name| {
+                member_name.str == name.str && member_name.span.start != name.span.start
+            })
+        }) {
+            Some(member) => member.clone(),
+            None => self.error_token("no such member"),
+        }
+    }
+
+    /// Rejects declaring a variable, parameter or member of type `void` or of
+    /// an incomplete struct or union type.
+    fn check_object_type(&self, ty: &Type) {
+        let token = ty.name.as_ref().unwrap_or(self.token());
+        if ty.is_void() {
+            self.error_tok(token, "variable declared void");
+        }
+        if let TypeKind::Array { len: None, .. } = ty.kind {
+            self.error_tok(token, format!("array size missing in '{}'", token.str));
+        }
+        if ty.is_incomplete() {
+            self.error_tok(
+                token,
+                format!("'{}' has incomplete type '{}'", token.str, ty),
+            );
+        }
+    }
+
+    /// func-params = ("void" | param ("," param)* ("," "...")?)? ")"
+    ///
+    /// Each named parameter is declared as a local variable in a scope of its
+    /// own, so that later parameters can use it in their array bounds, as in
+    /// `int f(int n, int a[][n])`.
+    fn func_params(&mut self, ty: Type) -> Type {
+        let token = self.tokens[self.index - 1].clone();
+        let mut params = Vec::new();
+        let mut vars = Vec::new();
+        let mut is_variadic = false;
+        let is_prototyped = !self.equal(')');
+        self.enter_scope();
+
+        if self.equal("void") && self.next_equal(")") {
+            self.next();
+            self.next();
+        } else {
+            while !self.consume(')') {
+                log::debug!("type_suffix token={:?}", self.token());
+                if !params.is_empty() {
+                    self.expect(",");
+                    if self.consume("...") {
+                        is_variadic = true;
+                        self.expect(')');
+                        break;
+                    }
+                }
+                let basety = self.declspec(None);
+                let ty = self.declarator(basety);
+                // A parameter of array type is adjusted to a pointer to its
+                // element type, and one of function type to a pointer to the
+                // function.
+                let ty = match &ty.kind {
+                    TypeKind::Array { base, .. } | TypeKind::Vla { base, .. } => Type {
+                        name: ty.name.clone(),
+                        ..base.clone().pointer_to()
+                    },
+                    TypeKind::Func { .. } => Type {
+                        name: ty.name.clone(),
+                        ..ty.pointer_to()
+                    },
+                    _ => ty,
+                };
+                self.check_object_type(&ty);
+                if let Some(name) = ty.name.as_ref().and_then(|name| name.get_ident()) {
+                    vars.push(self.add_lvar(name, ty.clone(), ty.align()));
+                }
+                params.push(ty);
+            }
+        }
+        self.leave_scope();
+
+        // Suffixes after the parameter list apply to the return type, as in
+        // `int f(void)[4]`, which C does not allow.
+        let ty = self.type_suffix(ty);
+        match ty.kind {
+            TypeKind::Array { .. } => self.error_tok(
+                &token,
+                format!("function cannot return array type '{}'", ty),
+            ),
+            TypeKind::Func { .. } => self.error_tok(
+                &token,
+                format!("function cannot return function type '{}'", ty),
+            ),
+            _ => (),
+        }
+        self.param_vars = vars;
+        ty.func_type(params, is_variadic, is_prototyped)
+    }
+
+    fn type_suffix(&mut self, ty: Type) -> Type {
+        if self.consume("(") {
+            return self.func_params(ty);
+        }
+
+        if self.consume('[') {
+            let token = self.token().clone();
+            let len = if self.consume(']') {
+                None
+            } else {
+                let mut len = self.assign();
+                self.add_type(&mut len);
+                if !len.ty.as_ref().unwrap().is_integer() {
+                    self.error_tok(&len.token, "size of array has non-integer type");
+                }
+                self.expect(']');
+                Some(len)
+            };
+            let base = self.type_suffix(ty);
+            if let TypeKind::Func { .. } = base.kind {
+                self.error_tok(
+                    &token,
+                    format!("array of functions of type '{}' is not allowed", base),
+                );
+            }
+            if base.size().is_none() && !base.is_vla() {
+                self.error_tok(
+                    &token,
+                    format!("array type has incomplete element type '{}'", base),
+                );
+            }
+            // In a function, an array whose length is not a constant, or whose
+            // elements are variable-length arrays, is a variable-length array.
+            return match len {
+                Some(len) if self.in_function() && (base.is_vla() || !len.is_const_expr()) => {
+                    let size_var = self.add_lvar(String::new(), Type::type_long(), 8);
+                    base.vla_of(len, size_var)
+                }
+                Some(len) => {
+                    let len = self.eval(&len);
+                    if len < 0 {
+                        self.error_tok(&token, "size of array is negative");
+                    }
+                    base.array_of(len as u64)
+                }
+                None => base.incomplete_array_of(),
+            };
+        }
+        ty
+    }
+
+    /// declarator = pointers ("(" declarator ")" | ident?) type-suffix
+    fn declarator(&mut self, ty: Type) -> Type {
+        let ty = self.pointers(ty);
+
+        // In `int (*x)(int)` the suffix after the parentheses applies first,
+        // so skip over the nested declarator, read the suffix, and then come
+        // back to parse the nested declarator with the resulting type as its
+        // base. A `(` starting a parameter list is a suffix instead.
+        if self.equal('(') && !self.next_is_type_name() && !self.next_equal(")") {
+            let start = self.index;
+            self.skip_parens();
+            let ty = self.type_suffix(ty);
+            let end = self.index;
+            self.index = start + 1;
+            let ty = self.declarator(ty);
+            self.index = end;
+            return ty;
+        }
+
+        let name = match self.token().kind {
+            TokenKind::Ident => {
+                let name = self.token().clone();
+                self.next();
+                Some(name)
+            }
+            _ => None,
+        };
+        log::debug!("declarator token={:?}", self.token());
+        let mut ty = self.type_suffix(ty);
+        ty.name = name;
+        ty
+    }
+
+    /// Skips from a `(` to just past its matching `)`.
+    fn skip_parens(&mut self) {
+        let start = self.token().clone();
+        let mut depth = 0;
+        loop {
+            if let TokenKind::Eof = self.token().kind {
+                self.error_tok(&start, "unbalanced parentheses");
+            }
+            if self.equal('(') {
+                depth += 1;
+            } else if self.equal(')') {
+                depth -= 1;
+            }
+            self.next();
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Returns the identifier declared by `ty`, which must not be omitted.
+    fn get_name(&self, ty: &Type) -> String {
+        match ty.name.as_ref().and_then(|name| name.get_ident()) {
+            Some(name) => name,
+            None => self.error_token(format!(
+                "expected a variable name, got `{}`",
+                self.token().str
+            )),
+        }
+    }
+
+    /// abstract-declarator = pointers ("(" abstract-declarator ")")? type-suffix
+    fn abstract_declarator(&mut self, ty: Type) -> Type {
+        let ty = self.pointers(ty);
+
+        // As in `declarator`, the suffix after a nested declarator applies
+        // first, so `int (*)[3]` is a pointer to an array.
+        if self.equal('(') && !self.next_is_type_name() && !self.next_equal(")") {
+            let start = self.index;
+            self.skip_parens();
+            let ty = self.type_suffix(ty);
+            let end = self.index;
+            self.index = start + 1;
+            let ty = self.abstract_declarator(ty);
+            self.index = end;
+            return ty;
+        }
+
+        self.type_suffix(ty)
+    }
+
+    /// type-name = declspec abstract-declarator
+    fn typename(&mut self) -> Type {
+        let basety = self.declspec(None);
+        self.abstract_declarator(basety)
+    }
+
+    /// initializer = string-literal
+    ///             | "{" (initializer ("," initializer)* ","?)? "}"
+    ///             | assign
+    ///
+    /// Reads the initializer of an object of type `ty`. A string literal
+    /// initializes an array of characters.
+    fn initializer(&mut self, ty: &Type) -> Initializer {
+        match &ty.kind {
+            TypeKind::Array { base, len } => {
+                let children = match self.token().kind.clone() {
+                    TokenKind::Str { str, .. } if matches!(base.kind, TypeKind::Char { .. }) => {
+                        let token = self.token().clone();
+                        self.next();
+                        // The terminating null character is dropped if the
+                        // array has no room for it.
+                        let chars: Vec<char> = str.chars().chain(Some('\0')).collect();
+                        let len = len.unwrap_or(chars.len() as u64);
+                        if len + 1 < chars.len() as u64 {
+                            self.warn_tok(
+                                &token,
+                                "initializer-string for array of 'char' is too long",
+                            );
+                        }
+                        chars
+                            .iter()
+                            .take(len as usize)
+                            .map(|&ch| Initializer {
+                                ty: *base.clone(),
+                                expr: Some(Node::new_node_num(ch as u8 as i64, &token)),
+                                children: Vec::new(),
+                            })
+                            .collect()
+                    }
+                    _ => {
+                        if !self.equal('{') {
+                            self.error_token("invalid initializer");
+                        }
+                        self.initializer_list(|_| Some((**base).clone()), *len)
+                    }
+                };
+                let len = len.unwrap_or(children.len() as u64);
+                Initializer {
+                    ty: Type {
+                        name: ty.name.clone(),
+                        qual: ty.qual,
+                        ..base.clone().array_of(len)
+                    },
+                    expr: None,
+                    children,
+                }
+            }
+            TypeKind::Struct { .. } | TypeKind::Union { .. } if self.equal('{') => {
+                // Unnamed bit-fields and flexible array members are skipped,
+                // and only the first member of a union can be initialized.
+                let members: Vec<Member> = ty
+                    .members()
+                    .unwrap()
+                    .iter()
+                    .filter(|member| member.name.is_some() && !member.ty.is_incomplete())
+                    .cloned()
+                    .collect();
+                let count = match ty.kind {
+                    TypeKind::Union { .. } => members.len().min(1),
+                    _ => members.len(),
+                };
+                let children = self
+                    .initializer_list(|i| members.get(i).map(|m| m.ty.clone()), Some(count as u64));
+                Initializer {
+                    ty: ty.clone(),
+                    expr: None,
+                    children,
+                }
+            }
+            _ => {
+                // A scalar may be enclosed in braces.
+                let has_braces = self.consume('{');
+                let mut expr = self.assign();
+                self.add_type(&mut expr);
+                if has_braces {
+                    self.consume(',');
+                    self.expect('}');
+                }
+                self.check_pointer_qualifiers(
+                    &expr.token,
+                    ty,
+                    expr.ty.as_ref().unwrap(),
+                    "initialization",
+                );
+                Initializer {
+                    ty: ty.clone(),
+                    expr: Some(expr),
+                    children: Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// Reads a braced list of initializers, the `i`th of which initializes
+    /// an object of type `ty_of(i)`. Elements beyond `len` are diagnosed and
+    /// discarded.
+    fn initializer_list(
+        &mut self,
+        ty_of: impl Fn(usize) -> Option<Type>,
+        len: Option<u64>,
+    ) -> Vec<Initializer> {
+        self.expect('{');
+        let mut children = Vec::new();
+        let mut i = 0;
+        while !self.consume('}') {
+            if i > 0 {
+                self.expect(',');
+                if self.consume('}') {
+                    break;
+                }
+            }
+            if len.is_some_and(|len| i as u64 >= len) {
+                self.warn_tok(&self.token().clone(), "excess elements in initializer");
+                self.skip_excess_element();
+            } else {
+                let ty = ty_of(i).unwrap();
+                children.push(self.initializer(&ty));
+            }
+            i += 1;
         }
-
-        Node::new_unary(
-            NodeKind::Member(self.get_struct_member(lhs.ty.clone().unwrap())),
-            lhs.clone(),
-            self.token(),
-        )
+        children
     }
 
-    fn get_struct_member(&self, ty: Type) -> Member {
-        if let TypeKind::Struct { members, .. } = ty.kind {
-            log::debug!(
-                "struct members={:?} token name={} token loc={}",
-                members,
-                self.token().str,
-                self.token().loc
-            );
-            let name = self.token().clone();
-            let member = members
-                .iter()
-                .find(|member| {
-                    member.name.as_ref().map_or(false, |member_name| {
-                        member_name.str == name.str && member_name.loc != name.loc
-                    })
-                })
-                .unwrap();
-            return member.clone();
+    fn skip_excess_element(&mut self) {
+        if self.consume('{') {
+            while !self.consume('}') {
+                self.skip_excess_element();
+                self.consume(',');
+            }
+            return;
         }
-        self.error_token("no such member");
-        unreachable!()
+        self.assign();
     }
 
-    fn func_params(&mut self, ty: Type) -> Type {
-        let mut params = Vec::new();
-
-        while !self.consume(')') {
-            log::debug!("type_suffix token={:?}", self.token());
-            if params.len() > 0 {
-                self.expect(",");
-            }
-            let basety = self.declspec();
-            let ty = self.declarator(basety);
-            params.push(ty);
+    /// Declares the variable-length array `ty` of `size` bytes, and appends to
+    /// `body` the code that allocates it on the stack. The enclosing block
+    /// frees it by restoring `rsp`, which it saves on entry.
+    fn alloc_vla(&mut self, ty: &Type, size: &Var, body: &mut Vec<Node>) {
+        if self.scope.front().unwrap().vla_sp.is_none() {
+            let vla_sp = self.add_lvar(String::new(), Type::type_long(), 8);
+            self.scope.front_mut().unwrap().vla_sp = Some(vla_sp);
         }
-
-        ty.func_type(params)
+        let lvar = self.add_lvar(self.get_name(ty), ty.clone(), 8);
+        let size = Node::new_node_var(size.clone(), size.ty.clone(), self.token());
+        body.push(Node::new_unary(
+            NodeKind::VlaAlloc(lvar),
+            size,
+            self.token(),
+        ));
     }
 
-    fn type_suffix(&mut self, ty: Type) -> Type {
-        if self.consume("(") {
-            return self.func_params(ty);
+    /// Appends to `body` the assignments that set the object `lhs` as
+    /// `init` says.
+    fn init_assignments(&self, init: Initializer, lhs: Node, body: &mut Vec<Node>) {
+        let token = lhs.token.clone();
+        if let Some(expr) = init.expr {
+            let mut lhs = lhs;
+            self.add_type(&mut lhs);
+            // An initializer may set a const object, so it is written through
+            // an unqualified lvalue.
+            lhs.ty = lhs.ty.map(|ty| ty.unqualified());
+            let node = Node::new_binary(NodeKind::Assign, lhs, expr, &token);
+            body.push(Node::new_unary(NodeKind::ExprStmt, node, &token));
+            return;
         }
 
-        if self.consume('[') {
-            let sz = self.get_number();
-            self.next();
-            self.expect(']');
-            return self.type_suffix(ty.clone()).array_of(sz);
+        match &init.ty.kind {
+            TypeKind::Array { .. } => {
+                for (i, child) in init.children.into_iter().enumerate() {
+                    let elem =
+                        self.new_add(lhs.clone(), Node::new_node_num(i as i64, &token), &token);
+                    let elem = Node::new_unary(NodeKind::Deref, elem, &token);
+                    self.init_assignments(child, elem, body);
+                }
+            }
+            _ => {
+                let members = init.ty.members().unwrap();
+                let members = members.iter().filter(|member| member.name.is_some());
+                for (member, child) in members.zip(init.children) {
+                    let node =
+                        Node::new_unary(NodeKind::Member(member.clone()), lhs.clone(), &token);
+                    self.init_assignments(child, node, body);
+                }
+            }
         }
-        ty
     }
 
-    fn declarator(&mut self, ty: Type) -> Type {
-        let mut ty = ty;
-        while self.consume('*') {
-            ty = ty.pointer_to();
-        }
+    /// Returns the contents of a global variable set by `init`, whose
+    /// expressions must be compile-time constants.
+    fn gvar_data(&self, init: &Initializer) -> Vec<u8> {
+        let mut data = vec![0; init.ty.size().unwrap() as usize];
+        self.write_gvar_data(init, &mut data, 0);
+        data
+    }
 
-        if !matches!(self.token().kind, TokenKind::Ident) {
-            panic!("expected a variable name, got {:?}", self.token());
+    fn write_gvar_data(&self, init: &Initializer, data: &mut [u8], offset: u64) {
+        if let Some(expr) = &init.expr {
+            let offset = offset as usize;
+            match init.ty.kind {
+                TypeKind::Float { .. } => {
+                    let val = self.eval_double(expr) as f32;
+                    data[offset..offset + 4].copy_from_slice(&val.to_le_bytes());
+                    return;
+                }
+                TypeKind::Double { .. } => {
+                    let val = self.eval_double(expr);
+                    data[offset..offset + 8].copy_from_slice(&val.to_le_bytes());
+                    return;
+                }
+                TypeKind::LongDouble { .. } => {
+                    let val = self.eval_x87(expr);
+                    data[offset..offset + 10].copy_from_slice(&val);
+                    return;
+                }
+                _ => (),
+            }
+            // Converting the value to the type of the object also truncates
+            // it and turns it into a `_Bool`.
+            let val = self.eval(&self.new_cast(expr.clone(), init.ty.unqualified()));
+            let size = init.ty.size().unwrap() as usize;
+            data[offset..offset + size].copy_from_slice(&val.to_le_bytes()[..size]);
+            return;
         }
 
-        let func_name = self.token().clone();
-        self.next();
-        log::debug!("declarator token={:?}", self.token());
-        let mut ty = self.type_suffix(ty);
-        ty.name = Some(func_name);
-        ty
+        match &init.ty.kind {
+            TypeKind::Array { base, .. } => {
+                let size = base.size().unwrap();
+                for (i, child) in init.children.iter().enumerate() {
+                    self.write_gvar_data(child, data, offset + size * i as u64);
+                }
+            }
+            _ => {
+                let members = init.ty.members().unwrap();
+                let members = members.iter().filter(|member| member.name.is_some());
+                for (member, child) in members.zip(&init.children) {
+                    let offset = offset + member.offset;
+                    if !member.is_bitfield {
+                        self.write_gvar_data(child, data, offset);
+                        continue;
+                    }
+                    let size = member.ty.size().unwrap() as usize;
+                    let range = offset as usize..offset as usize + size;
+                    let mut unit = [0; 8];
+                    unit[..size].copy_from_slice(&data[range.clone()]);
+                    let mut unit = u64::from_le_bytes(unit);
+                    let mask = if member.bit_width == 64 {
+                        u64::MAX
+                    } else {
+                        (1 << member.bit_width) - 1
+                    };
+                    let expr = child.expr.clone().unwrap();
+                    let val = self.eval(&self.new_cast(expr, member.ty.unqualified())) as u64;
+                    unit &= !(mask << member.bit_offset);
+                    unit |= (val & mask) << member.bit_offset;
+                    data[range].copy_from_slice(&unit.to_le_bytes()[..size]);
+                }
+            }
+        }
     }
 
     fn declaration(&mut self) -> Node {
-        let basety = self.declspec();
+        let mut attr = VarAttr::default();
+        let basety = self.declspec(Some(&mut attr));
         let mut body = Vec::new();
 
+        if attr.is_typedef {
+            self.parse_typedef(basety);
+            return Node::new_block(body, self.token());
+        }
+
         let mut i = 0;
         while !self.consume(';') {
             if i > 0 {
@@ -492,16 +1624,47 @@ impl Tokens {
             i += 1;
 
             let ty = self.declarator(basety.clone());
-            let lvar = self.add_lvar(ty.clone().name.unwrap().get_ident().unwrap(), ty.clone());
-            let lhs = Node::new_node_var(lvar, ty, self.token());
+            if let TypeKind::Func { .. } = ty.kind {
+                self.declare_function(&ty);
+                continue;
+            }
+            if attr.is_extern {
+                self.declare_gvar(ty);
+                continue;
+            }
 
-            if !self.consume('=') {
+            for node in self.vla_sizes(&ty) {
+                let token = node.token.clone();
+                body.push(Node::new_unary(NodeKind::ExprStmt, node, &token));
+            }
+            if let TypeKind::Vla { size_var, .. } = &ty.kind {
+                if self.equal('=') {
+                    self.error_token("variable-sized object may not be initialized");
+                }
+                self.alloc_vla(&ty, size_var, &mut body);
                 continue;
             }
 
-            let rhs = self.assign();
-            let node = Node::new_binary(NodeKind::Assign, lhs, rhs, self.token());
-            body.push(Node::new_unary(NodeKind::ExprStmt, node, self.token()));
+            // The initializer is read first, since it may give the length of
+            // an array.
+            let init = if self.consume('=') {
+                Some(self.initializer(&ty))
+            } else {
+                None
+            };
+            let ty = init.as_ref().map_or(ty, |init| init.ty.clone());
+            self.check_object_type(&ty);
+            let align = self.var_align(&ty, &attr);
+            let lvar = self.add_lvar(self.get_name(&ty), ty.clone(), align);
+
+            let Some(init) = init else {
+                continue;
+            };
+            if init.expr.is_none() {
+                body.push(Node::new(NodeKind::MemZero(lvar.clone()), self.token()));
+            }
+            let lhs = Node::new_node_var(lvar, ty, self.token());
+            self.init_assignments(init, lhs, &mut body);
         }
 
         log::debug!("body={:?}", body);
@@ -528,8 +1691,8 @@ impl Tokens {
                 let els = self.stmt();
                 if let NodeKind::If { cond, then, .. } = node.kind {
                     node.kind = NodeKind::If {
-                        cond: cond,
-                        then: then,
+                        cond,
+                        then,
                         els: Some(Box::new(els)),
                     };
                 }
@@ -572,16 +1735,57 @@ impl Tokens {
             return Node::new(
                 NodeKind::For {
                     init: Box::new(init),
-                    cond: cond.map(|c| Box::new(c)),
-                    inc: inc.map(|i| Box::new(i)),
+                    cond: cond.map(Box::new),
+                    inc: inc.map(Box::new),
                     then: Box::new(then),
                 },
                 self.token(),
             );
         };
 
-        if self.consume("return") {
-            let node = Node::new_unary(NodeKind::Return, self.expr(), self.token());
+        if self.equal("return") {
+            let token = self.token().clone();
+            self.next();
+            if self.consume(';') {
+                return Node::new(NodeKind::Return, &token);
+            }
+
+            let mut expr = self.expr();
+            self.add_type(&mut expr);
+            if self.return_ty.as_ref().is_some_and(|ty| ty.is_void()) {
+                self.error_tok(&token, "void function should not return a value");
+            }
+            if let Err(e) = expr.check_not_void() {
+                self.error_tok(&e.token, e.msg);
+            }
+            match self.return_ty.clone() {
+                Some(ty)
+                    if ty.is_struct_or_union()
+                        || expr.ty.as_ref().unwrap().is_struct_or_union() =>
+                {
+                    let expr_ty = expr.ty.as_ref().unwrap();
+                    if !ty.is_compatible(expr_ty) {
+                        self.error_tok(
+                            &expr.token,
+                            format!(
+                                "incompatible types when returning type '{}' but '{}' was expected",
+                                expr_ty, ty
+                            ),
+                        );
+                    }
+                }
+                Some(ty) => {
+                    self.check_pointer_qualifiers(
+                        &expr.token,
+                        &ty,
+                        expr.ty.as_ref().unwrap(),
+                        "return",
+                    );
+                    expr = self.new_cast(expr, ty);
+                }
+                None => (),
+            }
+            let node = Node::new_unary(NodeKind::Return, expr, &token);
             self.expect(';');
             return node;
         };
@@ -609,11 +1813,12 @@ impl Tokens {
             } else {
                 self.stmt()
             };
-            node.add_type();
+            self.add_type(&mut node);
             body.push(node);
         }
+        let vla_sp = self.scope.front_mut().unwrap().vla_sp.take();
         self.leave_scope();
-        Node::new_block(body, self.token())
+        Node::new(NodeKind::Block { body, vla_sp }, self.token())
     }
 
     fn expr_stmt(&mut self) -> Node {
@@ -629,60 +1834,134 @@ impl Tokens {
         let mut node = self.mul();
 
         loop {
+            let token = self.token().clone();
             if self.consume('+') {
-                node = Node::new_add(node, self.mul(), self.token());
+                let rhs = self.mul();
+                node = self.new_add(node, rhs, &token);
             } else if self.consume('-') {
-                node = Node::new_sub(node, self.mul(), self.token())
+                let rhs = self.mul();
+                node = self.new_sub(node, rhs, &token);
             } else {
                 return node;
             }
         }
     }
 
+    /// mul = cast ("*" cast | "/" cast)*
     fn mul(&mut self) -> Node {
-        let mut node = self.unary();
+        let mut node = self.cast();
 
         loop {
+            let token = self.token().clone();
             if self.consume("*") {
-                node = Node::new_binary(NodeKind::Mul, node, self.unary(), self.token());
+                node = Node::new_binary(NodeKind::Mul, node, self.cast(), &token);
             } else if self.consume("/") {
-                node = Node::new_binary(NodeKind::Div, node, self.unary(), self.token());
+                node = Node::new_binary(NodeKind::Div, node, self.cast(), &token);
             } else {
                 return node;
             }
         }
     }
 
-    /// unary = ("+" | "-" | "*" | "&") unary
+    /// cast = "(" type-name ")" cast
+    ///      | unary
+    fn cast(&mut self) -> Node {
+        if !(self.equal('(') && self.next_is_type_name()) {
+            return self.unary();
+        }
+
+        let start = self.token().clone();
+        self.next();
+        let ty = self.typename();
+        self.expect(')');
+        let mut expr = self.cast();
+        self.add_type(&mut expr);
+
+        match ty.kind {
+            TypeKind::Array { .. } | TypeKind::Vla { .. } => {
+                self.error_tok(&start, "cast specifies array type")
+            }
+            TypeKind::Func { .. } => self.error_tok(&start, "cast specifies function type"),
+            _ => (),
+        }
+        if !ty.is_void() {
+            if !ty.is_numeric() && !ty.is_pointer() {
+                self.error_tok(&start, "conversion to non-scalar type requested");
+            }
+            let expr_ty = expr.ty.as_ref().unwrap();
+            if ty.is_pointer() && expr_ty.is_flonum() {
+                self.error_tok(&start, "cannot convert to a pointer type");
+            }
+            if ty.is_flonum() && expr_ty.is_pointer() {
+                self.error_tok(
+                    &start,
+                    "pointer value used where a floating-point was expected",
+                );
+            }
+            if let Err(e) = expr.check_not_void() {
+                self.error_tok(&e.token, e.msg);
+            }
+            if expr.ty.as_ref().is_some_and(|ty| ty.is_struct_or_union()) {
+                self.error_tok(
+                    &expr.token,
+                    "aggregate value used where a scalar was expected",
+                );
+            }
+        }
+
+        Node {
+            token: start,
+            ..self.new_cast(expr, ty)
+        }
+    }
+
+    /// unary = ("+" | "-" | "*" | "&") cast
     ///       | postfix
     fn unary(&mut self) -> Node {
         if self.consume('+') {
-            return self.unary();
+            return self.cast();
         } else if self.consume('-') {
-            return Node::new_binary(
-                NodeKind::Sub,
-                Node::new_node_num(0, self.token()),
-                self.unary(),
-                self.token(),
-            );
+            return Node::new_unary(NodeKind::Neg, self.cast(), self.token());
         } else if self.consume('&') {
-            return Node::new_unary(NodeKind::Addr, self.unary(), self.token());
+            return Node::new_unary(NodeKind::Addr, self.cast(), self.token());
         } else if self.consume('*') {
-            return Node::new_unary(NodeKind::Deref, self.unary(), self.token());
+            return Node::new_unary(NodeKind::Deref, self.cast(), self.token());
         }
         self.postfix()
     }
 
     fn postfix(&mut self) -> Node {
+        let start = self.index;
         let mut node = self.primary();
 
         loop {
+            if self.equal('(') {
+                let name = self.source_text(start);
+                self.add_type(&mut node);
+                let func_ty = match node.ty.as_ref() {
+                    Some(ty) if matches!(ty.kind, TypeKind::Func { .. }) => ty.clone(),
+                    Some(Type {
+                        kind: TypeKind::Ptr { base, .. },
+                        ..
+                    }) if matches!(base.kind, TypeKind::Func { .. }) => *base.clone(),
+                    _ => self.error_tok(
+                        &node.token,
+                        format!(
+                            "called object '{}' is not a function or function pointer",
+                            name
+                        ),
+                    ),
+                };
+                node = self.funcall(node, &name, Some(func_ty));
+                continue;
+            }
+
             if self.consume('[') {
                 let idx = self.expr();
                 self.expect(']');
                 node = Node::new_unary(
                     NodeKind::Deref,
-                    Node::new_add(node, idx, self.token()),
+                    self.new_add(node, idx, self.token()),
                     self.token(),
                 );
                 continue;
@@ -694,6 +1973,23 @@ impl Tokens {
                 continue;
             }
 
+            if self.equal("->") {
+                // `x->y` is short for `(*x).y`.
+                let token = self.token().clone();
+                self.add_type(&mut node);
+                let ty = node.ty.clone().unwrap();
+                if !ty.base().is_some_and(|base| base.is_struct_or_union()) {
+                    self.error_tok(
+                        &token,
+                        format!("invalid type argument of '->' (have '{}')", ty),
+                    );
+                }
+                self.next();
+                node = self.struct_ref(&mut Node::new_unary(NodeKind::Deref, node, &token));
+                self.next();
+                continue;
+            }
+
             return node;
         }
     }
@@ -701,16 +1997,16 @@ impl Tokens {
     fn primary(&mut self) -> Node {
         if self.consume('(') {
             if self.consume('{') {
-                let mut body = self.compound_stmt().body().unwrap();
-                if let Some(last_node) = body.pop() {
-                    body.push(*last_node.lhs.unwrap());
+                let NodeKind::Block { mut body, vla_sp } = self.compound_stmt().kind else {
+                    unreachable!("compound statement is not a block");
+                };
+                match body.pop() {
+                    Some(Node {
+                        lhs: Some(expr), ..
+                    }) => body.push(*expr),
+                    _ => self.error_token("statement expression returning void is not supported"),
                 }
-                let node = Node::new(
-                    NodeKind::StmtExpr {
-                        body: Box::new(body),
-                    },
-                    self.token(),
-                );
+                let node = Node::new(NodeKind::StmtExpr { body, vla_sp }, self.token());
                 self.expect(')');
                 return node;
             }
@@ -719,21 +2015,116 @@ impl Tokens {
             return node;
         }
 
+        if self.equal("_Alignof") {
+            let token = self.token().clone();
+            self.next();
+            let ty = if self.equal('(') && self.next_is_type_name() {
+                self.next();
+                let ty = self.typename();
+                self.expect(')');
+                ty
+            } else {
+                let mut node = self.unary();
+                self.add_type(&mut node);
+                self.check_not_bitfield(&node, "_Alignof");
+                node.ty.unwrap()
+            };
+            if ty.size().is_none() {
+                self.error_tok(
+                    &token,
+                    format!(
+                        "invalid application of '_Alignof' to incomplete type '{}'",
+                        ty
+                    ),
+                );
+            }
+            return Node::new_node_ulong(ty.align() as i64, &token);
+        }
+
+        if self.equal("sizeof")
+            && self.next_equal("(")
+            && self.is_type_name_token(&self.tokens[self.index + 2])
+        {
+            let token = self.token().clone();
+            self.next();
+            self.next();
+            let ty = self.typename();
+            self.expect(')');
+            return match ty.size() {
+                Some(size) => Node::new_node_ulong(size as i64, &token),
+                // The sizes of the arrays in the type name are computed where
+                // it appears.
+                None if ty.is_vla() => {
+                    let size = self.new_cast(self.vla_size(&ty, &token), Type::type_ulong());
+                    self.vla_sizes(&ty)
+                        .into_iter()
+                        .rev()
+                        .fold(size, |node, size| {
+                            let size = Node::new_unary(NodeKind::ExprStmt, size, &token);
+                            Node::new_binary(NodeKind::Comma, size, node, &token)
+                        })
+                }
+                None => self.error_tok(
+                    &token,
+                    format!(
+                        "invalid application of 'sizeof' to incomplete type '{}'",
+                        ty
+                    ),
+                ),
+            };
+        }
+
         if self.consume("sizeof") {
             let mut node = self.unary();
-            node.add_type();
-            return Node::new_node_num(node.ty.unwrap().size().unwrap(), self.token());
+            self.add_type(&mut node);
+            self.check_not_bitfield(&node, "sizeof");
+            let ty = node.ty.unwrap();
+            return match ty.size() {
+                Some(size) => Node::new_node_ulong(size as i64, self.token()),
+                None if ty.is_vla() => {
+                    self.new_cast(self.vla_size(&ty, &node.token), Type::type_ulong())
+                }
+                None => self.error_tok(
+                    &node.token,
+                    format!(
+                        "invalid application of 'sizeof' to incomplete type '{}'",
+                        ty
+                    ),
+                ),
+            };
         }
 
         if let TokenKind::Ident = self.token().kind {
-            if self.next_equal("(") {
-                return self.funcall();
+            if self.next_equal("(") && self.find_var().is_none() {
+                let token = self.token().clone();
+                self.warn_tok(
+                    &token,
+                    format!("implicit declaration of function '{}'", token.str),
+                );
+                let var = Var {
+                    id: 0,
+                    name: token.str.clone(),
+                    offset: 0,
+                    ty: Type::type_int().func_type(vec![], false, false),
+                    is_local: false,
+                    init_data: None,
+                    align: 1,
+                };
+                let callee = Node::new_node_var(var.clone(), var.ty, &token);
+                self.next();
+                return self.funcall(callee, &token.str, None);
             }
 
-            let var = self.find_var();
-            let node = match var {
-                Some(var) => Node::new_node_var(var.clone(), var.ty.clone(), self.token()),
-                None => panic!(
+            let node = match self.find_scope(&self.token().str) {
+                Some(VarScope { var: Some(var), .. }) => {
+                    Node::new_node_var(var.clone(), var.ty.clone(), self.token())
+                }
+                Some(VarScope {
+                    enum_ty: Some(_),
+                    enum_val,
+                    ..
+                }) => Node::new_node_num(*enum_val, self.token()),
+                _ => panic!(
                     "undefined variable: {:?}, locals={:?}, global={:?}, scope={:?}",
                     self.token(),
                     self.locals,
@@ -747,77 +2138,435 @@ impl Tokens {
         }
 
         if let TokenKind::Str { ty, str } = self.token().clone().kind {
-            let var = self.new_string_literal(*ty, str);
+            let var = self.new_string_literal(*ty, &str);
             log::debug!("string literal: {:?}", var);
             self.next();
             return Node::new_node_var(var.clone(), var.ty, self.token());
         }
 
-        if let TokenKind::Num(val) = self.token().kind {
-            let node = Node::new_node_num(val, self.token());
+        if let TokenKind::Num { val, ty } = self.token().clone().kind {
+            let mut node = Node::new_node_num(val, self.token());
+            node.ty = Some(*ty);
+            self.next();
+            return node;
+        }
+
+        if let TokenKind::FNum { val, x87, ty } = self.token().clone().kind {
+            let x87 = x87.map(|x87| *x87);
+            let mut node = Node::new(NodeKind::FNum { val, x87 }, self.token());
+            node.ty = Some(*ty);
             self.next();
             return node;
         }
 
-        panic!("primary: unexpected token {:?}", self.token());
+        if let TokenKind::Eof = self.token().kind {
+            self.error_token("unexpected end of file");
+        }
+        self.error_token(format!("unexpected token: `{}`", self.token().str));
     }
 
-    fn function(&mut self) -> Function {
-        let ty = self.declspec();
-        let ty = self.declarator(ty);
-        self.locals = LinkedList::new();
+    /// Declares or defines the function whose declarator has been read as
+    /// `ty`, returning the function if it has a body.
+    fn function(&mut self, ty: Type) -> Option<Function> {
+        self.declare_function(&ty);
+        if self.consume(';') {
+            return None;
+        }
+
+        let name = self.get_name(&ty);
+        log::debug!("function name={:?}", name);
+        if self.functions.iter().any(|function| function.name == name) {
+            self.error_tok(
+                ty.name.as_ref().unwrap(),
+                format!("redefinition of '{}'", name),
+            );
+        }
+
+        let param_vars = std::mem::take(&mut self.param_vars);
         self.enter_scope();
 
-        if let TypeKind::Func { params, .. } = ty.clone().kind {
+        if let TypeKind::Func {
+            params, return_ty, ..
+        } = ty.clone().kind
+        {
+            self.return_ty = return_ty.map(|ty| *ty);
             let mut func_params = LinkedList::new();
 
+            // A struct or union too large to return in registers is written
+            // through a pointer the caller passes as a hidden first argument.
+            if let Some(ty) = self
+                .return_ty
+                .clone()
+                .filter(|ty| ty.is_returned_in_memory())
+            {
+                let ty = ty.pointer_to();
+                let lvar = self.add_lvar(String::new(), ty.clone(), ty.align());
+                func_params.push_back(lvar);
+            }
+
             log::debug!("function params={:?}", params);
-            for param in params.iter() {
-                let lvar = self.add_lvar(
-                    param.clone().name.unwrap().get_ident().unwrap(),
-                    param.clone(),
-                );
-                func_params.push_back(lvar.clone())
+            if params.iter().any(|param| param.name.is_none()) {
+                self.error_tok(ty.name.as_ref().unwrap(), "parameter name omitted");
+            }
+            // The sizes of variable-length arrays in the parameter types,
+            // such as that of `*a` in `int a[][n]`, are computed on entry.
+            let mut body = Vec::new();
+            for var in param_vars {
+                for node in self.vla_sizes(&var.ty) {
+                    let token = node.token.clone();
+                    body.push(Node::new_unary(NodeKind::ExprStmt, node, &token));
+                }
+                self.push_scope(var.name.clone()).var = Some(var.clone());
+                func_params.push_back(var);
             }
             log::debug!("function token={:?}", self.token());
 
-            let name = ty.clone().name.unwrap().get_ident().unwrap();
-            log::debug!("function name={:?}", name);
-
             self.expect('{');
+            body.push(self.compound_stmt());
             let function = Function {
                 name,
-                body: self.compound_stmt(),
+                body: Node::new_block(body, self.token()),
                 params: func_params,
                 locals: self.locals.clone(),
                 stack_size: None,
             };
             self.leave_scope();
-            return function;
+            self.return_ty = None;
+            return Some(function);
         }
         unreachable!("ty is not function")
     }
 
-    fn funcall(&mut self) -> Node {
-        let start = self.token().clone();
-        self.next();
-        self.next();
+    /// funcall = "(" (assign ("," assign)*)? ")"
+    ///
+    /// Parses the arguments of a call to `callee`, whose type is `func_ty`,
+    /// or `None` if the function was implicitly declared. `name` is the
+    /// source text of `callee`, for diagnostics.
+    fn funcall(&mut self, callee: Node, name: &str, func_ty: Option<Type>) -> Node {
+        self.expect('(');
         let mut args = Vec::new();
         while !self.consume(')') {
-            if args.len() > 0 {
-                log::debug!("args len={}", args.len());
+            if !args.is_empty() {
                 self.expect(',');
             }
-            args.push(self.assign());
+            let mut arg = self.assign();
+            self.add_type(&mut arg);
+            if let Err(e) = arg.check_not_void() {
+                self.error_tok(&e.token, e.msg);
+            }
+            args.push(arg);
         }
-        log::debug!("tokentokentoken={:?}", self.token());
-        Node::new(
-            NodeKind::FuncCall {
-                name: start.str,
+
+        let args = match &func_ty {
+            Some(func_ty) => self.convert_args(&callee.token, name, func_ty, args),
+            None => args.into_iter().map(|arg| self.promote_arg(arg)).collect(),
+        };
+        let ret_buffer = match func_ty.as_ref().map(|ty| &ty.kind) {
+            Some(TypeKind::Func {
+                return_ty: Some(return_ty),
+                ..
+            }) if return_ty.is_struct_or_union() => {
+                let ty = *return_ty.clone();
+                Some(self.add_lvar(String::new(), ty.clone(), ty.align()))
+            }
+            _ => None,
+        };
+        let token = callee.token.clone();
+        Node {
+            kind: NodeKind::FuncCall {
                 args,
+                func_ty,
+                ret_buffer,
             },
-            self.token(),
-        )
+            lhs: Some(Box::new(callee)),
+            rhs: None,
+            ty: None,
+            token,
+        }
+    }
+
+    /// Returns the source text from the token at index `start` to the last
+    /// token read.
+    fn source_text(&self, start: usize) -> String {
+        let begin = self.tokens[start].span.start;
+        let end = self.tokens[self.index - 1].span.end;
+        self.file.contents[begin..end].to_string()
+    }
+
+    /// Checks the arguments of a call to `name` against its prototype
+    /// `func_ty` and converts each one to its parameter type as if by
+    /// assignment. Arguments matching the `...` of a variadic function, or
+    /// passed to a function without a prototype, only undergo the default
+    /// argument promotions.
+    fn convert_args(
+        &self,
+        token: &Token,
+        name: &str,
+        func_ty: &Type,
+        args: Vec<Node>,
+    ) -> Vec<Node> {
+        let (params, is_variadic) = match &func_ty.kind {
+            TypeKind::Func {
+                is_prototyped: false,
+                ..
+            } => return args.into_iter().map(|arg| self.promote_arg(arg)).collect(),
+            TypeKind::Func {
+                params,
+                is_variadic,
+                ..
+            } => (params, *is_variadic),
+            _ => unreachable!("callee is not a function"),
+        };
+
+        if args.len() < params.len() {
+            self.error_tok(token, format!("too few arguments to function '{}'", name));
+        }
+
+        let mut converted = Vec::new();
+        for (i, arg) in args.into_iter().enumerate() {
+            let param = match params.get(i) {
+                Some(param) => param,
+                None if is_variadic => {
+                    converted.push(self.promote_arg(arg));
+                    continue;
+                }
+                None => self.error_tok(
+                    &arg.token,
+                    format!("too many arguments to function '{}'", name),
+                ),
+            };
+
+            let arg_ty = arg.ty.as_ref().unwrap();
+            let arg_is_pointer =
+                arg_ty.is_pointer() || matches!(arg_ty.kind, TypeKind::Func { .. });
+            self.check_pointer_qualifiers(
+                &arg.token,
+                param,
+                arg_ty,
+                &format!("passing argument {} of '{}'", i + 1, name),
+            );
+            let is_null = arg.is_null_pointer_constant();
+            let mismatch =
+                if matches!(param.kind, TypeKind::Ptr { .. }) && arg_ty.is_integer() && !is_null {
+                    Some("makes pointer from integer without a cast")
+                } else if param.is_integer() && !param.is_bool() && arg_is_pointer {
+                    Some("makes integer from pointer without a cast")
+                } else if !param.is_compatible(arg_ty)
+                    && (param.is_struct_or_union() || arg_ty.is_struct_or_union())
+                    || param.is_pointer() && arg_ty.is_flonum()
+                    || param.is_flonum() && arg_is_pointer
+                {
+                    Some("has incompatible type")
+                } else {
+                    None
+                };
+            if let Some(msg) = mismatch {
+                self.error_tok(
+                    &arg.token,
+                    format!("passing argument {} of '{}' {}", i + 1, name, msg),
+                );
+            }
+
+            if param.is_struct_or_union() {
+                converted.push(arg);
+                continue;
+            }
+            let ty = Type {
+                name: None,
+                ..param.clone()
+            };
+            converted.push(self.new_cast(arg, ty));
+        }
+        converted
+    }
+
+    /// Applies the default argument promotions to an argument passed without
+    /// a parameter type to convert it to: small integers become `int` and
+    /// `float` becomes `double`.
+    fn promote_arg(&self, arg: Node) -> Node {
+        let ty = arg.ty.as_ref().unwrap();
+        match ty.size() {
+            _ if matches!(ty.kind, TypeKind::Float { .. }) => {
+                self.new_cast(arg, Type::type_double())
+            }
+            Some(size) if size < 4 && ty.is_integer() => self.new_cast(arg, Type::type_int()),
+            _ => arg,
+        }
+    }
+
+    /// Parses an integer constant expression and evaluates it.
+    fn const_expr(&mut self) -> i64 {
+        let mut node = self.equality();
+        self.add_type(&mut node);
+        self.eval(&node)
+    }
+
+    /// Returns the nodes that compute the sizes of the variable-length arrays
+    /// in `ty` at run time, innermost first.
+    fn vla_sizes(&self, ty: &Type) -> Vec<Node> {
+        match &ty.kind {
+            TypeKind::Ptr { base, .. } | TypeKind::Array { base, .. } => self.vla_sizes(base),
+            TypeKind::Vla {
+                len,
+                base,
+                size_var,
+            } => {
+                let mut nodes = self.vla_sizes(base);
+                let token = &len.token;
+                let base_size = match base.size() {
+                    Some(size) => Node::new_node_long(size as i64, token),
+                    None => self.vla_size(base, token),
+                };
+                let len = self.new_cast(*len.clone(), Type::type_long());
+                let size = Node::new_binary(NodeKind::Mul, len, base_size, token);
+                let lhs = Node::new_node_var(*size_var.clone(), size_var.ty.clone(), token);
+                let mut node = Node::new_binary(NodeKind::Assign, lhs, size, token);
+                self.add_type(&mut node);
+                nodes.push(node);
+                nodes
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the hidden local holding the size of the variable-length
+    /// array `ty`.
+    fn vla_size(&self, ty: &Type, token: &Token) -> Node {
+        match &ty.kind {
+            TypeKind::Vla { size_var, .. } => {
+                Node::new_node_var(*size_var.clone(), size_var.ty.clone(), token)
+            }
+            _ => unreachable!("not a variable-length array"),
+        }
+    }
+
+    /// Returns whether the body or parameters of a function are being parsed,
+    /// rather than declarations at file scope.
+    fn in_function(&self) -> bool {
+        self.scope.len() > 1
+    }
+
+    /// Evaluates a constant expression at compile time.
+    fn eval(&self, node: &Node) -> i64 {
+        let lhs = || self.eval(node.lhs.as_ref().unwrap());
+        let rhs = || self.eval(node.rhs.as_ref().unwrap());
+        let flhs = || self.eval_double(node.lhs.as_ref().unwrap());
+        let frhs = || self.eval_double(node.rhs.as_ref().unwrap());
+        let is_unsigned = |node: &Option<Box<Node>>| {
+            node.as_ref()
+                .and_then(|node| node.ty.as_ref())
+                .is_some_and(|ty| ty.is_unsigned)
+        };
+        let is_flonum = |node: &Option<Box<Node>>| {
+            node.as_ref()
+                .and_then(|node| node.ty.as_ref())
+                .is_some_and(|ty| ty.is_flonum())
+        };
+
+        match node.kind {
+            NodeKind::Num(val) => val,
+            NodeKind::Add => lhs().wrapping_add(rhs()),
+            NodeKind::Sub => lhs().wrapping_sub(rhs()),
+            NodeKind::Neg => lhs().wrapping_neg(),
+            NodeKind::Mul => lhs().wrapping_mul(rhs()),
+            NodeKind::Div => {
+                let (lhs, rhs) = (lhs(), rhs());
+                if rhs == 0 {
+                    self.error_tok(&node.token, "division by zero in a constant expression");
+                }
+                if is_unsigned(&node.lhs) {
+                    ((lhs as u64) / (rhs as u64)) as i64
+                } else {
+                    lhs.wrapping_div(rhs)
+                }
+            }
+            NodeKind::Eq if is_flonum(&node.lhs) => (flhs() == frhs()) as i64,
+            NodeKind::Ne if is_flonum(&node.lhs) => (flhs() != frhs()) as i64,
+            NodeKind::Lt if is_flonum(&node.lhs) => (flhs() < frhs()) as i64,
+            NodeKind::Le if is_flonum(&node.lhs) => (flhs() <= frhs()) as i64,
+            NodeKind::Eq => (lhs() == rhs()) as i64,
+            NodeKind::Ne => (lhs() != rhs()) as i64,
+            NodeKind::Lt if is_unsigned(&node.lhs) => ((lhs() as u64) < (rhs() as u64)) as i64,
+            NodeKind::Lt => (lhs() < rhs()) as i64,
+            NodeKind::Le if is_unsigned(&node.lhs) => ((lhs() as u64) <= (rhs() as u64)) as i64,
+            NodeKind::Le => (lhs() <= rhs()) as i64,
+            NodeKind::Comma => rhs(),
+            NodeKind::Cast => {
+                let ty = node.ty.as_ref().unwrap();
+                if is_flonum(&node.lhs) && ty.is_bool() {
+                    return (flhs() != 0.0) as i64;
+                }
+                let val = match is_flonum(&node.lhs) {
+                    true if ty.is_unsigned => flhs() as u64 as i64,
+                    true => flhs() as i64,
+                    false => lhs(),
+                };
+                if ty.is_bool() {
+                    return (val != 0) as i64;
+                }
+                match (ty.size(), ty.is_unsigned) {
+                    (Some(1), false) => val as i8 as i64,
+                    (Some(1), true) => val as u8 as i64,
+                    (Some(2), false) => val as i16 as i64,
+                    (Some(2), true) => val as u16 as i64,
+                    (Some(4), false) => val as i32 as i64,
+                    (Some(4), true) => val as u32 as i64,
+                    _ => val,
+                }
+            }
+            _ => self.error_tok(&node.token, "not a compile-time constant"),
+        }
+    }
+
+    /// Evaluates a constant expression of floating type at compile time. The
+    /// value of a `float` expression is rounded to `float` precision, and a
+    /// `long double` one only has the precision of a `double`.
+    fn eval_double(&self, node: &Node) -> f64 {
+        let ty = node.ty.as_ref().unwrap();
+        if ty.is_integer() {
+            let val = self.eval(node);
+            return if ty.is_unsigned {
+                val as u64 as f64
+            } else {
+                val as f64
+            };
+        }
+
+        let lhs = || self.eval_double(node.lhs.as_ref().unwrap());
+        let rhs = || self.eval_double(node.rhs.as_ref().unwrap());
+        let val = match node.kind {
+            NodeKind::FNum { val, .. } => val,
+            NodeKind::Add => lhs() + rhs(),
+            NodeKind::Sub => lhs() - rhs(),
+            NodeKind::Neg => -lhs(),
+            NodeKind::Mul => lhs() * rhs(),
+            NodeKind::Div => lhs() / rhs(),
+            NodeKind::Comma => rhs(),
+            NodeKind::Cast => lhs(),
+            _ => self.error_tok(&node.token, "not a compile-time constant"),
+        };
+        match ty.kind {
+            TypeKind::Float { .. } => val as f32 as f64,
+            _ => val,
+        }
+    }
+
+    /// Evaluates a constant expression converted to `long double` at compile
+    /// time. Only a constant, possibly negated, keeps its full precision.
+    fn eval_x87(&self, node: &Node) -> [u8; 10] {
+        let is_long_double = |node: &Node| node.ty.as_ref().is_some_and(|ty| ty.is_long_double());
+        match &node.kind {
+            NodeKind::FNum { x87: Some(x87), .. } if is_long_double(node) => *x87,
+            NodeKind::Neg if is_long_double(node) => {
+                let mut x87 = self.eval_x87(node.lhs.as_ref().unwrap());
+                x87[9] ^= 0x80;
+                x87
+            }
+            NodeKind::Cast if node.lhs.as_deref().is_some_and(is_long_double) => {
+                self.eval_x87(node.lhs.as_ref().unwrap())
+            }
+            _ => x87_bytes(self.eval_double(node)),
+        }
     }
 
     fn equality(&mut self) -> Node {
@@ -856,7 +2605,7 @@ impl Tokens {
         let token = self.token();
         let op = op.into();
         if matches!(token.kind, TokenKind::Keyword) && matches!(token.kind, TokenKind::Punct)
-            || token.str.to_string() != op
+            || token.str != op
         {
             self.error_token(format!("expected: `{}`, actual: `{}`", op, token.str))
         }
@@ -867,7 +2616,7 @@ impl Tokens {
         let token = self.token();
         let op = op.into();
         if matches!(token.kind, TokenKind::Keyword) && matches!(token.kind, TokenKind::Punct)
-            || token.str.to_string() != op
+            || token.str != op
         {
             return false;
         }
@@ -879,7 +2628,7 @@ impl Tokens {
         let token = self.token();
         let op = op.into();
         if !matches!(token.kind, TokenKind::Keyword) && !matches!(token.kind, TokenKind::Punct)
-            || token.str.to_string() != op
+            || token.str != op
         {
             return false;
         }
@@ -890,7 +2639,7 @@ impl Tokens {
         if let Some(token) = self.next_token() {
             let op = op.into();
             if !matches!(token.kind, TokenKind::Keyword) && !matches!(token.kind, TokenKind::Punct)
-                || token.str.to_string() != op
+                || token.str != op
             {
                 return false;
             }
@@ -899,20 +2648,67 @@ impl Tokens {
     }
 
     fn is_type_name(&self) -> bool {
-        self.equal("int") || self.equal("char") || self.equal("struct")
-    }
-
-    fn error_token(&self, msg: impl Into<String>) {
-        panic!(
-            "{}\n{}^ {} {}:{}",
-            self.tokens
-                .iter()
-                .map(|token| token.str.clone())
-                .collect::<String>(),
-            (1..self.index).map(|_| " ").collect::<String>(),
-            msg.into(),
-            self.token().line_number,
-            self.token().loc,
-        )
+        self.is_type_name_token(self.token())
+    }
+
+    fn next_is_type_name(&self) -> bool {
+        self.next_token()
+            .is_some_and(|token| self.is_type_name_token(token))
+    }
+
+    /// Returns whether `token` begins a type name: a type keyword, or an
+    /// identifier that is currently declared as a typedef name.
+    fn is_type_name_token(&self, token: &Token) -> bool {
+        match token.kind {
+            TokenKind::Keyword => TYPE_NAMES.contains(&token.str.as_str()),
+            _ => self.find_typedef(token).is_some(),
+        }
+    }
+
+    fn new_cast(&self, expr: Node, ty: Type) -> Node {
+        Node::new_cast(expr, ty).unwrap_or_else(|e| self.error_tok(&e.token, e.msg))
+    }
+
+    fn add_type(&self, node: &mut Node) {
+        if let Err(e) = node.add_type() {
+            self.error_tok(&e.token, e.msg);
+        }
+    }
+
+    /// Warns if converting a value of type `from` to the pointer type `to`
+    /// drops qualifiers of the pointed-to type, as `char *p = "x"` would not
+    /// but `char *p = (const char *)s` would. `context` describes the
+    /// conversion.
+    fn check_pointer_qualifiers(&self, token: &Token, to: &Type, from: &Type, context: &str) {
+        let (to, from) = match (&to.kind, from.base()) {
+            (TypeKind::Ptr { base, .. }, Some(from)) => (base, from),
+            _ => return,
+        };
+        for qual in from.qual.missing_from(to.qual).names() {
+            self.warn_tok(
+                token,
+                format!(
+                    "{} discards '{}' qualifier from pointer target type",
+                    context, qual
+                ),
+            );
+        }
+    }
+
+    fn warn_tok(&self, token: &Token, msg: impl Into<String>) {
+        let msg = format!("warning: {}", msg.into());
+        eprintln!("{}", self.file.error_at(token.span, token.line_number, msg));
+    }
+
+    fn error_token(&self, msg: impl Into<String>) -> ! {
+        self.error_tok(self.token(), msg)
+    }
+
+    fn error_tok(&self, token: &Token, msg: impl Into<String>) -> ! {
+        let mut msg = msg.into();
+        if token.expanded {
+            msg.push_str(" (in a macro expansion)");
+        }
+        panic!("{}", self.file.error_at(token.span, token.line_number, msg))
     }
 }
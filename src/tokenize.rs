@@ -1,231 +1,532 @@
-use crate::{Token, TokenKind, Type};
-use std::str::Chars;
-
-fn error_at(c: char, input: Chars, index: usize, error: String) -> String {
-    let loc: Vec<char> = input
-        .clone()
-        .enumerate()
-        .filter(|(idx, _)| idx <= &index)
-        .map(|(_, v)| v)
-        .collect();
-
-    String::from(format!(
-        "{}{}",
-        input.clone().collect::<String>(),
+use crate::{SourceFile, Span, Token, TokenKind, Type};
+
+impl SourceFile {
+    /// Formats `error` with the source line containing `span` and a caret
+    /// underlining the offending range.
+    pub fn error_at(&self, span: Span, line_number: usize, error: impl Into<String>) -> String {
+        let input = &self.contents;
+        let loc = span.start;
+        let start = input[..loc].rfind('\n').map_or(0, |idx| idx + 1);
+        let end = input[loc..].find('\n').map_or(input.len(), |idx| loc + idx);
+        let prefix = format!("{}:{}: ", self.name, line_number);
+        let width = input[loc..span.end.clamp(loc, end)].chars().count();
+
         format!(
-            "{}^ {}",
-            (1..loc.len()).map(|_| " ").collect::<String>(),
-            error
+            "{}{}\n{}^{} {}",
+            prefix,
+            &input[start..end],
+            " ".repeat(prefix.len() + input[start..loc].chars().count()),
+            "~".repeat(width.saturating_sub(1)),
+            error.into()
         )
-    ))
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            file_id: self.id,
+            start,
+            end,
+        }
+    }
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, str: impl Into<String>, loc: usize, line_number: usize) -> Self {
-        let tok = Self {
+    pub fn new(kind: TokenKind, str: impl Into<String>, span: Span, line_number: usize) -> Self {
+        Self {
             kind,
             str: str.into(),
-            loc,
+            span,
             line_number,
-        };
-        tok
+            expanded: false,
+        }
     }
 
-    pub fn tokenize(p: String) -> Result<Vec<Token>, String> {
+    /// Splits `file` into tokens in a single pass over its bytes.
+    ///
+    /// The returned tokens always end with an `Eof` token located at the end
+    /// of the input.
+    pub fn tokenize(file: &SourceFile) -> Result<Vec<Token>, String> {
+        let p = &file.contents;
+        let src = p.as_bytes();
         let mut tokens = vec![];
-
         let mut line_number = 1;
-        let chars = p.chars();
-        let chars_vec = p.chars().collect::<Vec<char>>();
-        let mut chars_iter = chars.clone().enumerate();
+        let mut i = 0;
 
-        while let Some((i, p)) = chars_iter.next() {
-            log::debug!("tokens={:?}", tokens);
+        while i < src.len() {
+            let c = src[i];
 
-            if p == '\n' {
+            if c == b'\n' {
                 line_number += 1;
+                i += 1;
+                continue;
             }
 
-            if is_line_comments(chars_vec.clone(), p, i) {
-                chars_iter.next();
-                while let Some((_, p)) = chars_iter.next() {
-                    if p == '\n' {
-                        break;
-                    }
+            if src[i..].starts_with(b"//") {
+                i += 2;
+                while i < src.len() && src[i] != b'\n' {
+                    i += 1;
                 }
                 continue;
             }
 
-            if is_block_comments(chars_vec.clone(), p, i) {
-                chars_iter.next();
-                match chars_vec[i + 1..].iter().collect::<String>().find("*/") {
-                    Some(idx) => {
-                        for _ in 0..idx {
-                            chars_iter.next();
-                        }
-                    }
-                    None => {
-                        return Err(error_at(
-                            p,
-                            chars,
-                            i,
-                            "unterminated block comment".to_string(),
+            if src[i..].starts_with(b"/*") {
+                let start = i;
+                let start_line = line_number;
+                i += 2;
+                loop {
+                    if i + 1 >= src.len() {
+                        return Err(file.error_at(
+                            file.span(start, start + 2),
+                            start_line,
+                            "unterminated block comment",
                         ));
                     }
+                    if src[i] == b'*' && src[i + 1] == b'/' {
+                        i += 2;
+                        break;
+                    }
+                    if src[i] == b'\n' {
+                        line_number += 1;
+                    }
+                    i += 1;
                 }
-                chars_iter.next();
-                chars_iter.next();
                 continue;
             }
 
-            if p.is_whitespace() {
+            if c.is_ascii_whitespace() {
+                i += 1;
                 continue;
             }
 
-            if p == '"' {
-                let token = read_string_literal(&mut chars_iter, i, line_number);
-                tokens.push(token?);
+            if c == b'"' {
+                let (token, end) = read_string_literal(file, i, line_number)?;
+                tokens.push(token);
+                i = end;
                 continue;
             }
 
-            if is_ident(p) {
-                let mut ident = p.to_string();
-                if let Some(next_c) = chars_vec.get(i + 1) {
-                    if !(is_ident(*next_c) || is_number(*next_c)) {
-                        tokens.push(Self::new(TokenKind::Ident, ident, i, line_number));
-                        continue;
-                    }
-                }
-                while let Some((i, c)) = chars_iter.next() {
-                    log::debug!("char={}", c);
-                    ident.push(c);
-                    if let Some(next_c) = chars_vec.get(i + 1) {
-                        if !(is_ident(*next_c) || is_number(*next_c)) {
-                            break;
-                        }
-                    }
+            if is_ident(c) {
+                let start = i;
+                i += 1;
+                while i < src.len() && (is_ident(src[i]) || is_number(src[i])) {
+                    i += 1;
                 }
-                tokens.push(Self::new(TokenKind::Ident, ident, i, line_number));
+                tokens.push(Self::new(
+                    TokenKind::Ident,
+                    &p[start..i],
+                    file.span(start, i),
+                    line_number,
+                ));
                 continue;
             }
 
-            if is_punctuators(p) {
-                let mut op = p.to_string();
-                if let Some(next_c) = chars_vec.get(i + 1) {
-                    if is_cmp_op(format!("{}{}", op, next_c)) {
-                        chars_iter.next();
-                        op.push(*next_c)
-                    };
-                }
-                tokens.push(Self::new(TokenKind::Punct, op, i, line_number));
+            if is_number(c) || c == b'.' && src.get(i + 1).is_some_and(|&c| is_number(c)) {
+                let (token, end) = read_number(file, i, line_number)?;
+                tokens.push(token);
+                i = end;
                 continue;
             }
 
-            if p.is_digit(10) {
-                let mut number = vec![p];
-                if let Some(next_c) = chars_vec.get(i + 1) {
-                    if !next_c.is_digit(10) {
-                        tokens.push(Self::new(
-                            TokenKind::Num(
-                                number
-                                    .iter()
-                                    .collect::<String>()
-                                    .parse::<u16>()
-                                    .or_else(|_| {
-                                        Err(format!("cannot convert char to integer: {:?}", number))
-                                    })?,
-                            ),
-                            p,
-                            i,
-                            line_number,
-                        ));
-                        continue;
-                    }
-                }
-                let mut idx = i;
-                while let Some((i, c)) = chars_iter.next() {
-                    number.push(c);
-                    idx = 1;
-                    if let Some(next_c) = chars_vec.get(i + 1) {
-                        if !next_c.is_digit(10) {
-                            break;
-                        }
-                    }
-                }
+            if is_punctuators(c) {
+                let len = if src[i..].starts_with(b"...") {
+                    3
+                } else if src.len() > i + 1 && is_two_byte_punct(&src[i..i + 2]) {
+                    2
+                } else {
+                    1
+                };
                 tokens.push(Self::new(
-                    TokenKind::Num(number.iter().collect::<String>().parse::<u16>().or_else(
-                        |_| Err(format!("cannot convert char to integer: {:?}", number)),
-                    )?),
-                    p,
-                    idx,
+                    TokenKind::Punct,
+                    &p[i..i + len],
+                    file.span(i, i + len),
                     line_number,
                 ));
+                i += len;
                 continue;
-            };
-            return Err(error_at(p, chars, i, format!("invalid token: {}", p)));
+            }
+
+            let ch = p[i..].chars().next().unwrap_or_default();
+            return Err(file.error_at(
+                file.span(i, i + ch.len_utf8()),
+                line_number,
+                format!("invalid token: {}", ch),
+            ));
         }
 
-        tokens.push(Self::new(TokenKind::Eof, "", 0, 0));
+        tokens.push(Self::new(
+            TokenKind::Eof,
+            "",
+            file.span(src.len(), src.len()),
+            line_number,
+        ));
         convert_keywords(&mut tokens);
         Ok(tokens)
     }
 }
 
-fn is_keyword(token: impl Into<String>) -> bool {
+fn is_keyword(token: &str) -> bool {
     [
-        "return", "if", "else", "while", "for", "int", "char", "sizeof", "struct",
+        "return", "if", "else", "while", "for", "void", "int", "char", "sizeof", "struct", "short",
+        "long", "signed", "unsigned", "_Bool", "typedef", "enum", "union", "_Alignof", "_Alignas",
+        "const", "volatile", "restrict", "extern", "float", "double",
     ]
-    .contains(&token.into().as_ref())
+    .contains(&token)
 }
 
-fn is_punctuators(ch: char) -> bool {
-    ch == '+'
-        || ch == '-'
-        || ch == '*'
-        || ch == '/'
-        || ch == '('
-        || ch == ')'
-        || ch == ';'
-        || ch == '>'
-        || ch == '<'
-        || ch == '='
-        || ch == '!'
-        || ch == '{'
-        || ch == '}'
-        || ch == '&'
-        || ch == ','
-        || ch == '['
-        || ch == ']'
-        || ch == '.'
-}
+/// Reads a numeric literal starting at byte offset `start` and returns the
+/// token together with the offset just past it.
+///
+/// The literal extends over every character a preprocessing number may
+/// contain, including the sign of an exponent, and is then read as a
+/// floating constant if it has a `.` or an exponent and as a decimal integer
+/// otherwise.
+fn read_number(
+    file: &SourceFile,
+    start: usize,
+    line_number: usize,
+) -> Result<(Token, usize), String> {
+    let p = &file.contents;
+    let src = p.as_bytes();
+    let mut end = start;
+    while end < src.len() {
+        if matches!(src[end], b'e' | b'E') && matches!(src.get(end + 1), Some(b'+' | b'-')) {
+            end += 2;
+        } else if is_ident(src[end]) || is_number(src[end]) || src[end] == b'.' {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+
+    let number = &p[start..end];
+    let span = file.span(start, end);
+    if number.contains(['.', 'e', 'E']) {
+        let (digits, ty) = if let Some(digits) = number.strip_suffix(['f', 'F']) {
+            (digits, Type::type_float())
+        } else if let Some(digits) = number.strip_suffix(['l', 'L']) {
+            (digits, Type::type_ldouble())
+        } else {
+            (number, Type::type_double())
+        };
+        let error = || {
+            file.error_at(
+                span,
+                line_number,
+                format!("invalid floating constant: {}", number),
+            )
+        };
+        let val = digits.parse::<f64>().map_err(|_| error())?;
+        let x87 = match ty.is_long_double() {
+            true => Some(Box::new(parse_x87(digits).ok_or_else(error)?)),
+            false => None,
+        };
+        let token = Token::new(
+            TokenKind::FNum {
+                val,
+                x87,
+                ty: Box::new(ty),
+            },
+            number,
+            span,
+            line_number,
+        );
+        return Ok((token, end));
+    }
 
-fn is_cmp_op(op: String) -> bool {
-    op == "==" || op == "!=" || op == "<=" || op == ">="
+    let digits_len = number.bytes().take_while(|&c| is_number(c)).count();
+    let digits = &number[..digits_len];
+    let val = digits.parse::<u64>().map_err(|_| {
+        file.error_at(
+            span,
+            line_number,
+            format!("cannot convert to integer: {}", number),
+        )
+    })?;
+    let ty = int_literal_type(val, &number[digits_len..]).ok_or_else(|| {
+        file.error_at(
+            span,
+            line_number,
+            format!("invalid integer suffix: {}", number),
+        )
+    })?;
+    let token = Token::new(
+        TokenKind::Num {
+            val: val as i64,
+            ty: Box::new(ty),
+        },
+        number,
+        span,
+        line_number,
+    );
+    Ok((token, end))
 }
 
-fn is_ident(ch: char) -> bool {
-    ('a'..='z').contains(&ch) || ('A'..='Z').contains(&ch) || ch == '_'
+/// Returns the type of a decimal integer literal with value `val` and the
+/// given suffix, or `None` if the suffix is invalid.
+fn int_literal_type(val: u64, suffix: &str) -> Option<Type> {
+    let suffix = suffix.to_ascii_lowercase();
+    let (u, l) = match suffix.as_str() {
+        "" => (false, false),
+        "u" => (true, false),
+        "l" | "ll" => (false, true),
+        "ul" | "lu" | "ull" | "llu" => (true, true),
+        _ => return None,
+    };
+
+    Some(if u && l {
+        Type::type_ulong()
+    } else if l {
+        if val >> 63 > 0 {
+            Type::type_ulong()
+        } else {
+            Type::type_long()
+        }
+    } else if u {
+        if val >> 32 > 0 {
+            Type::type_ulong()
+        } else {
+            Type::type_uint()
+        }
+    } else if val >> 63 > 0 {
+        Type::type_ulong()
+    } else if val >> 31 > 0 {
+        Type::type_long()
+    } else {
+        Type::type_int()
+    })
 }
 
-fn is_number(ch: char) -> bool {
-    ('0'..='9').contains(&ch)
+/// Converts the decimal floating constant `digits` to the x87 80-bit extended
+/// precision format, rounding to nearest even like gcc. Returns `None` if
+/// `digits` is malformed.
+fn parse_x87(digits: &str) -> Option<[u8; 10]> {
+    let (mantissa, exp) = match digits.find(['e', 'E']) {
+        Some(idx) => (&digits[..idx], &digits[idx + 1..]),
+        None => (digits, "0"),
+    };
+    let is_negative = exp.starts_with('-');
+    let exp_digits = exp.strip_prefix(['+', '-']).unwrap_or(exp);
+    if exp_digits.is_empty() || !exp_digits.bytes().all(is_number) {
+        return None;
+    }
+    // Far beyond the range of the format, the exact exponent doesn't matter.
+    let exp = exp_digits.parse::<i64>().unwrap_or(i64::MAX).min(100_000);
+    let exp = if is_negative { -exp } else { exp };
+
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_part.is_empty() && frac_part.is_empty()
+        || !int_part.bytes().chain(frac_part.bytes()).all(is_number)
+    {
+        return None;
+    }
+
+    // The value is `n * 10^dec_exp`.
+    let mut n = BigUint::default();
+    for c in int_part.bytes().chain(frac_part.bytes()) {
+        n.mul_add(10, (c - b'0') as u32);
+    }
+    if n.bit_len() == 0 {
+        return Some([0; 10]);
+    }
+    let dec_exp = exp - frac_part.len() as i64;
+    let magnitude = int_part.len() as i64 + frac_part.len() as i64 + dec_exp;
+    if magnitude > 4934 {
+        return Some(X87_INFINITY);
+    }
+    if magnitude < -4952 {
+        return Some([0; 10]);
+    }
+
+    // Compute `q`, the value scaled by `2^k` and truncated to an integer of
+    // at least 66 bits, so that the rounding bit is known. `inexact` tells
+    // whether anything was truncated.
+    let (q, k, inexact) = if dec_exp >= 0 {
+        for _ in 0..dec_exp {
+            n.mul_add(10, 0);
+        }
+        let k = 66u64.saturating_sub(n.bit_len());
+        (n.shl(k), -(k as i64), false)
+    } else {
+        let mut d = BigUint::default();
+        d.mul_add(1, 1);
+        for _ in 0..-dec_exp {
+            d.mul_add(10, 0);
+        }
+        let k = (66 + d.bit_len()).saturating_sub(n.bit_len());
+        let (q, rem) = n.shl(k).div_rem(&d);
+        (q, -(k as i64), rem.bit_len() > 0)
+    };
+
+    // Below the smallest normal exponent, fewer significand bits are left.
+    let len = q.bit_len() as i64;
+    let exp = len - 1 + k;
+    let precision = 64 - (-16382 - exp).max(0);
+    let shift = len - precision;
+    let mut significand = (0..64).fold(0u128, |acc, i| acc | (q.bit(shift + i) as u128) << i);
+    let round = q.bit(shift - 1);
+    let sticky = inexact || (0..shift - 1).any(|i| q.bit(i));
+    if round && (sticky || significand & 1 == 1) {
+        significand += 1;
+    }
+
+    let mut biased_exp = if precision < 64 { 0 } else { exp + 16383 };
+    if significand >> 64 != 0 {
+        significand >>= 1;
+        biased_exp += 1;
+    }
+    if biased_exp == 0 && significand >> 63 != 0 {
+        biased_exp = 1;
+    }
+    if biased_exp >= 0x7fff {
+        return Some(X87_INFINITY);
+    }
+
+    let mut bytes = [0; 10];
+    bytes[..8].copy_from_slice(&(significand as u64).to_le_bytes());
+    bytes[8..].copy_from_slice(&(biased_exp as u16).to_le_bytes());
+    Some(bytes)
 }
 
-fn is_line_comments(chars: Vec<char>, ch: char, i: usize) -> bool {
-    if let Some(next_c) = chars.get(i + 1) {
-        return format!("{}{}", ch, next_c) == "//";
+/// Positive infinity in the x87 extended precision format.
+const X87_INFINITY: [u8; 10] = [0, 0, 0, 0, 0, 0, 0, 0x80, 0xff, 0x7f];
+
+/// An unsigned integer of any size, as little-endian 32-bit words, for
+/// converting floating constants exactly.
+#[derive(Clone, Default, PartialEq)]
+struct BigUint(Vec<u32>);
+
+impl BigUint {
+    /// Sets `self` to `self * mul + add`.
+    fn mul_add(&mut self, mul: u32, add: u32) {
+        let mut carry = add as u64;
+        for word in self.0.iter_mut() {
+            let val = *word as u64 * mul as u64 + carry;
+            *word = val as u32;
+            carry = val >> 32;
+        }
+        if carry != 0 {
+            self.0.push(carry as u32);
+        }
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn bit_len(&self) -> u64 {
+        match self.0.last() {
+            Some(last) => self.0.len() as u64 * 32 - last.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    /// Returns bit `i`, which is zero for a negative `i`.
+    fn bit(&self, i: i64) -> bool {
+        i >= 0
+            && self
+                .0
+                .get(i as usize / 32)
+                .is_some_and(|word| word >> (i % 32) & 1 == 1)
+    }
+
+    fn shl(&self, bits: u64) -> Self {
+        let mut words = vec![0; bits as usize / 32];
+        let shift = bits % 32;
+        let mut carry = 0;
+        for &word in &self.0 {
+            let val = (word as u64) << shift | carry;
+            words.push(val as u32);
+            carry = val >> 32;
+        }
+        words.push(carry as u32);
+        let mut val = Self(words);
+        val.trim();
+        val
+    }
+
+    /// Subtracts `other`, which must not be larger than `self`.
+    fn sub_assign(&mut self, other: &Self) {
+        let mut borrow = 0;
+        for (i, word) in self.0.iter_mut().enumerate() {
+            let rhs = other.0.get(i).copied().unwrap_or(0) as i64 + borrow;
+            let val = *word as i64 - rhs;
+            *word = val as u32;
+            borrow = (val < 0) as i64;
+        }
+        self.trim();
+    }
+
+    /// Returns the quotient and remainder of dividing by `divisor`, by
+    /// binary long division.
+    fn div_rem(mut self, divisor: &Self) -> (Self, Self) {
+        let mut quotient = Self::default();
+        let len = divisor.bit_len();
+        for i in (0..=self.bit_len().saturating_sub(len)).rev() {
+            let shifted = divisor.shl(i);
+            if self >= shifted {
+                self.sub_assign(&shifted);
+                let word = i as usize / 32;
+                if quotient.0.len() <= word {
+                    quotient.0.resize(word + 1, 0);
+                }
+                quotient.0[word] |= 1 << (i % 32);
+            }
+        }
+        (quotient, self)
     }
-    false
 }
 
-fn is_block_comments(chars: Vec<char>, ch: char, i: usize) -> bool {
-    if let Some(next_c) = chars.get(i + 1) {
-        return format!("{}{}", ch, next_c) == "/*";
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(
+            self.0
+                .len()
+                .cmp(&other.0.len())
+                .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev())),
+        )
     }
-    false
 }
 
-fn convert_keywords(tokens: &mut Vec<Token>) {
+fn is_punctuators(ch: u8) -> bool {
+    matches!(
+        ch,
+        b'+' | b'-'
+            | b'*'
+            | b'/'
+            | b'('
+            | b')'
+            | b';'
+            | b'>'
+            | b'<'
+            | b'='
+            | b'!'
+            | b'{'
+            | b'}'
+            | b'&'
+            | b','
+            | b'['
+            | b']'
+            | b'.'
+            | b':'
+    )
+}
+
+fn is_two_byte_punct(op: &[u8]) -> bool {
+    matches!(op, b"==" | b"!=" | b"<=" | b">=" | b"->")
+}
+
+fn is_ident(ch: u8) -> bool {
+    ch.is_ascii_alphabetic() || ch == b'_'
+}
+
+fn is_number(ch: u8) -> bool {
+    ch.is_ascii_digit()
+}
+
+fn convert_keywords(tokens: &mut [Token]) {
     for token in tokens.iter_mut() {
         if let TokenKind::Ident = &token.kind {
             if is_keyword(&token.str) {
@@ -235,31 +536,34 @@ fn convert_keywords(tokens: &mut Vec<Token>) {
     }
 }
 
+/// Reads a string literal whose opening `"` is at byte offset `start` and
+/// returns the token together with the offset just past the closing `"`.
 fn read_string_literal(
-    chars: &mut impl Iterator<Item = (usize, char)>,
-    column_number: usize,
+    file: &SourceFile,
+    start: usize,
     line_number: usize,
-) -> Result<Token, String> {
-    let mut str = String::new();
-    while let Some((_, c)) = chars.next() {
-        log::debug!("string literal={}", c);
-        log::debug!("str={}", str);
-        if c == '\n' || c == '\0' {
-            return Err(format!("unclosed string literal: {:?}", c));
-        }
-        if c == '"' {
-            break;
-        }
-
-        str.push(c);
-        if c == '\\' {
-            str.push(chars.next().unwrap().1);
+) -> Result<(Token, usize), String> {
+    let input = &file.contents;
+    let src = input.as_bytes();
+    let mut end = start + 1;
+    loop {
+        match src.get(end) {
+            None | Some(b'\n') | Some(b'\0') => {
+                return Err(file.error_at(
+                    file.span(start, end.min(src.len())),
+                    line_number,
+                    "unclosed string literal",
+                ));
+            }
+            Some(b'"') => break,
+            Some(b'\\') => end += 2,
+            Some(_) => end += 1,
         }
     }
 
+    let str = &input[start + 1..end];
     let mut buf = String::new();
     let mut chars_iter = str.chars();
-    log::debug!("chars_iter={:?}", chars_iter);
     while let Some(c) = chars_iter.next() {
         if c == '\\' {
             buf.push_str(&read_escaped_char(&mut chars_iter));
@@ -267,38 +571,35 @@ fn read_string_literal(
             buf.push(c);
         }
     }
-    Ok(Token::new(
-        TokenKind::Str {
-            str: buf.clone(),
-            ty: Box::new(Type::type_char().array_of(buf.len() as u16 + 1)),
-        },
-        str,
-        column_number,
-        line_number,
+    Ok((
+        Token::new(
+            TokenKind::Str {
+                str: buf.clone(),
+                ty: Box::new(Type::type_char().array_of(buf.len() as u64 + 1)),
+            },
+            str,
+            file.span(start, end + 1),
+            line_number,
+        ),
+        end + 1,
     ))
 }
 
 fn from_hex(c: char) -> u8 {
-    if '0' <= c && c <= '9' {
-        return c as u8 - '0' as u8;
-    }
-    if 'a' <= c && c <= 'f' {
-        return c as u8 - 'a' as u8 + 10;
-    }
-    return c as u8 - 'A' as u8 + 10;
+    c.to_digit(16).unwrap_or(0) as u8
 }
 
 fn read_escaped_char(chars: &mut impl Iterator<Item = char>) -> String {
     let mut c = chars.next().unwrap();
-    if '0' <= c && c <= '7' {
-        let mut ch = c as u8 - '0' as u8;
+    if ('0'..='7').contains(&c) {
+        let mut ch = c as u8 - b'0';
         match chars.next() {
             Some(ch) => c = ch,
             None => return (ch as char).to_string(),
         }
         for _ in 0..2 {
-            if '0' <= c && c <= '7' {
-                ch = (ch << 3) + (c as u8 - '0' as u8);
+            if ('0'..='7').contains(&c) {
+                ch = (ch << 3) + (c as u8 - b'0');
                 match chars.next() {
                     Some(ch) => c = ch,
                     None => break,
@@ -312,13 +613,13 @@ fn read_escaped_char(chars: &mut impl Iterator<Item = char>) -> String {
         if let Some(ch) = chars.next() {
             c = ch
         }
-        if !c.is_digit(16) {
+        if !c.is_ascii_hexdigit() {
             panic!("invalid hex escape sequence");
         }
 
         let mut ch = from_hex(c);
-        while let Some(char) = chars.next() {
-            if !char.is_digit(16) {
+        for char in chars {
+            if !char.is_ascii_hexdigit() {
                 break;
             }
             ch = (ch << 4) + from_hex(char);
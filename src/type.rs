@@ -1,12 +1,26 @@
-use std::collections::LinkedList;
-
-use crate::{Member, Node, NodeKind, Token, Type, TypeKind};
+use crate::{
+    Member, Node, NodeKind, Qualifiers, StructBody, Token, Type, TypeError, TypeKind, Var,
+};
+use std::cell::OnceCell;
+use std::fmt;
+use std::rc::Rc;
 
 impl Type {
-    pub fn type_int() -> Self {
+    pub fn type_void() -> Self {
         Self {
-            kind: TypeKind::Int { size: 8 },
+            kind: TypeKind::Void,
             name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_bool() -> Self {
+        Self {
+            kind: TypeKind::Bool { size: 1 },
+            name: None,
+            is_unsigned: true,
+            qual: Qualifiers::default(),
         }
     }
 
@@ -14,42 +28,435 @@ impl Type {
         Self {
             kind: TypeKind::Char { size: 1 },
             name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_uchar() -> Self {
+        Self {
+            is_unsigned: true,
+            ..Self::type_char()
+        }
+    }
+
+    pub fn type_short() -> Self {
+        Self {
+            kind: TypeKind::Short { size: 2 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_ushort() -> Self {
+        Self {
+            is_unsigned: true,
+            ..Self::type_short()
+        }
+    }
+
+    pub fn type_int() -> Self {
+        Self {
+            kind: TypeKind::Int { size: 4 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
         }
     }
 
-    pub fn type_struct(members: Vec<Member>, size: u16, token: Token) -> Self {
+    pub fn type_uint() -> Self {
         Self {
-            kind: TypeKind::Struct { members, size },
-            name: Some(token),
+            is_unsigned: true,
+            ..Self::type_int()
+        }
+    }
+
+    pub fn type_long() -> Self {
+        Self {
+            kind: TypeKind::Long { size: 8 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_ulong() -> Self {
+        Self {
+            is_unsigned: true,
+            ..Self::type_long()
+        }
+    }
+
+    pub fn type_enum() -> Self {
+        Self {
+            kind: TypeKind::Enum { size: 4 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_float() -> Self {
+        Self {
+            kind: TypeKind::Float { size: 4 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_double() -> Self {
+        Self {
+            kind: TypeKind::Double { size: 8 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    pub fn type_ldouble() -> Self {
+        Self {
+            kind: TypeKind::LongDouble { size: 16 },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    /// Returns a new incomplete struct type, to be completed by `complete`.
+    pub fn type_struct(tag: Option<String>) -> Self {
+        Self {
+            kind: TypeKind::Struct {
+                tag,
+                body: Rc::new(OnceCell::new()),
+            },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    /// Returns a new incomplete union type, to be completed by `complete`.
+    pub fn type_union(tag: Option<String>) -> Self {
+        Self {
+            kind: TypeKind::Union {
+                tag,
+                body: Rc::new(OnceCell::new()),
+            },
+            name: None,
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    /// Completes a struct or union type with its members and size.
+    pub fn complete(&self, members: Vec<Member>, size: u64) {
+        if let TypeKind::Struct { body, .. } | TypeKind::Union { body, .. } = &self.kind {
+            body.set(StructBody { members, size })
+                .expect("type is already complete");
+        }
+    }
+
+    pub fn is_struct_or_union(&self) -> bool {
+        matches!(self.kind, TypeKind::Struct { .. } | TypeKind::Union { .. })
+    }
+
+    /// Returns whether the type is a struct or union whose definition has not
+    /// been seen yet, or an array of unknown length.
+    pub fn is_incomplete(&self) -> bool {
+        match &self.kind {
+            TypeKind::Array { len: None, .. } => true,
+            _ => self.is_struct_or_union() && self.members().is_none(),
         }
     }
 
     pub fn is_integer(&self) -> bool {
-        matches!(self.kind, TypeKind::Int { .. } | TypeKind::Char { .. })
+        matches!(
+            self.kind,
+            TypeKind::Bool { .. }
+                | TypeKind::Char { .. }
+                | TypeKind::Short { .. }
+                | TypeKind::Int { .. }
+                | TypeKind::Long { .. }
+                | TypeKind::Enum { .. }
+        )
+    }
+
+    /// Returns whether the type is a floating type. Values of `float` and
+    /// `double` are held in SSE registers, and those of `long double` on the
+    /// x87 register stack.
+    pub fn is_flonum(&self) -> bool {
+        matches!(
+            self.kind,
+            TypeKind::Float { .. } | TypeKind::Double { .. } | TypeKind::LongDouble { .. }
+        )
+    }
+
+    pub fn is_long_double(&self) -> bool {
+        matches!(self.kind, TypeKind::LongDouble { .. })
+    }
+
+    /// Returns whether `pred` holds for every scalar of the type, looking
+    /// through the members of structs and unions and the elements of arrays.
+    fn all_scalars(&self, pred: &impl Fn(&Type) -> bool) -> bool {
+        match &self.kind {
+            TypeKind::Struct { .. } | TypeKind::Union { .. } => self
+                .members()
+                .into_iter()
+                .flatten()
+                .all(|member| member.ty.all_scalars(pred)),
+            TypeKind::Array { base, .. } => base.all_scalars(pred),
+            _ => pred(self),
+        }
+    }
+
+    /// Returns whether the type is `long double` or has a `long double`
+    /// member. The System V ABI passes such values in memory.
+    pub fn has_long_double(&self) -> bool {
+        !self.all_scalars(&|ty| !ty.is_long_double())
+    }
+
+    /// Returns whether the type is a struct or union of 16 bytes made up of
+    /// `long double` only, which is returned in `st(0)` like a `long double`.
+    pub fn is_long_double_struct(&self) -> bool {
+        self.is_struct_or_union()
+            && self.size() == Some(16)
+            && self.all_scalars(&|ty| ty.is_long_double())
+    }
+
+    /// Returns whether the type is an arithmetic type: an integer or a
+    /// floating type.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_flonum()
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self.kind, TypeKind::Bool { .. })
+    }
+
+    pub fn is_void(&self) -> bool {
+        matches!(self.kind, TypeKind::Void)
+    }
+
+    /// Returns whether a value of the type is returned through a pointer
+    /// supplied by the caller rather than in registers, which the System V
+    /// ABI requires for structs and unions larger than 16 bytes, and for those
+    /// mixing `long double` with other members.
+    pub fn is_returned_in_memory(&self) -> bool {
+        self.is_struct_or_union()
+            && (self.size().unwrap_or(0) > 16
+                || self.has_long_double() && !self.is_long_double_struct())
+    }
+
+    /// Returns the type with its qualifiers removed, which is the type of
+    /// the value read from an lvalue of the type.
+    pub fn unqualified(&self) -> Self {
+        Self {
+            name: None,
+            qual: Qualifiers::default(),
+            ..self.clone()
+        }
     }
 
     pub fn is_pointer(&self) -> bool {
-        matches!(self.kind, TypeKind::Ptr { .. } | TypeKind::Array { .. })
+        matches!(
+            self.kind,
+            TypeKind::Ptr { .. } | TypeKind::Array { .. } | TypeKind::Vla { .. }
+        )
+    }
+
+    pub fn is_vla(&self) -> bool {
+        matches!(self.kind, TypeKind::Vla { .. })
     }
 
     pub fn base(&self) -> Option<Type> {
         match &self.kind {
-            TypeKind::Ptr { base, .. } | TypeKind::Array { base, .. } => Some(*base.clone()),
+            TypeKind::Ptr { base, .. }
+            | TypeKind::Array { base, .. }
+            | TypeKind::Vla { base, .. } => Some(*base.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn size(&self) -> Option<u64> {
+        match &self.kind {
+            TypeKind::Bool { size }
+            | TypeKind::Char { size }
+            | TypeKind::Short { size }
+            | TypeKind::Int { size }
+            | TypeKind::Long { size }
+            | TypeKind::Enum { size }
+            | TypeKind::Float { size }
+            | TypeKind::Double { size }
+            | TypeKind::LongDouble { size }
+            | TypeKind::Ptr { size, .. } => Some(*size),
+            TypeKind::Array { base, len } => Some(base.size()? * (*len)?),
+            TypeKind::Struct { body, .. } | TypeKind::Union { body, .. } => {
+                body.get().map(|body| body.size)
+            }
             _ => None,
         }
     }
 
-    pub fn size(&self) -> Option<u16> {
+    /// Returns whether `self` and `other` denote the same type, ignoring the
+    /// names of declarators.
+    pub fn is_compatible(&self, other: &Type) -> bool {
+        if self.is_unsigned != other.is_unsigned {
+            return false;
+        }
+
+        match (&self.kind, &other.kind) {
+            (TypeKind::Void, TypeKind::Void)
+            | (TypeKind::Bool { .. }, TypeKind::Bool { .. })
+            | (TypeKind::Char { .. }, TypeKind::Char { .. })
+            | (TypeKind::Short { .. }, TypeKind::Short { .. })
+            | (TypeKind::Int { .. }, TypeKind::Int { .. })
+            | (TypeKind::Long { .. }, TypeKind::Long { .. })
+            | (TypeKind::Float { .. }, TypeKind::Float { .. })
+            | (TypeKind::Double { .. }, TypeKind::Double { .. })
+            | (TypeKind::LongDouble { .. }, TypeKind::LongDouble { .. }) => true,
+            // Enumerated types are compatible with int, the type we give
+            // their values.
+            (TypeKind::Enum { .. } | TypeKind::Int { .. }, TypeKind::Enum { .. })
+            | (TypeKind::Enum { .. }, TypeKind::Int { .. }) => true,
+            (TypeKind::Ptr { base: base1, .. }, TypeKind::Ptr { base: base2, .. }) => {
+                base1.qual == base2.qual && base1.is_compatible(base2)
+            }
+            (
+                TypeKind::Array {
+                    base: base1,
+                    len: len1,
+                    ..
+                },
+                TypeKind::Array {
+                    base: base2,
+                    len: len2,
+                    ..
+                },
+            ) => {
+                // An array of unknown length is compatible with any length.
+                (len1.is_none() || len2.is_none() || len1 == len2)
+                    && base1.qual == base2.qual
+                    && base1.is_compatible(base2)
+            }
+            // The length of a variable-length array is only known at run
+            // time, so it is compatible with any array of the same elements.
+            (
+                TypeKind::Vla { base: base1, .. },
+                TypeKind::Array { base: base2, .. } | TypeKind::Vla { base: base2, .. },
+            )
+            | (TypeKind::Array { base: base1, .. }, TypeKind::Vla { base: base2, .. }) => {
+                base1.qual == base2.qual && base1.is_compatible(base2)
+            }
+            (
+                TypeKind::Func {
+                    params: params1,
+                    return_ty: return_ty1,
+                    is_variadic: is_variadic1,
+                    is_prototyped: is_prototyped1,
+                },
+                TypeKind::Func {
+                    params: params2,
+                    return_ty: return_ty2,
+                    is_variadic: is_variadic2,
+                    is_prototyped: is_prototyped2,
+                },
+            ) => {
+                let return_ty_matches = match (return_ty1, return_ty2) {
+                    (Some(ty1), Some(ty2)) => ty1.is_compatible(ty2),
+                    (ty1, ty2) => ty1.is_none() && ty2.is_none(),
+                };
+                // A function without a prototype says nothing about its
+                // parameters.
+                if !is_prototyped1 || !is_prototyped2 {
+                    return return_ty_matches;
+                }
+                return_ty_matches
+                    && is_variadic1 == is_variadic2
+                    && params1.len() == params2.len()
+                    && params1
+                        .iter()
+                        .zip(params2)
+                        .all(|(ty1, ty2)| ty1.is_compatible(ty2))
+            }
+            // A struct or union type is only compatible with itself.
+            (TypeKind::Struct { body: body1, .. }, TypeKind::Struct { body: body2, .. })
+            | (TypeKind::Union { body: body1, .. }, TypeKind::Union { body: body2, .. }) => {
+                Rc::ptr_eq(body1, body2)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the type both operands of a binary operator are converted to by
+    /// the usual arithmetic conversions. If either is floating, both become
+    /// the wider floating type. Otherwise operands narrower than `int` are
+    /// promoted to `int` first.
+    fn common_type(ty1: &Type, ty2: &Type) -> Type {
+        if let Some(base) = ty1.base() {
+            return base.pointer_to();
+        }
+        if let TypeKind::Func { .. } = ty1.kind {
+            return ty1.clone().pointer_to();
+        }
+
+        if ty1.is_long_double() || ty2.is_long_double() {
+            return Type::type_ldouble();
+        }
+        if let TypeKind::Double { .. } = ty1.kind {
+            return Type::type_double();
+        }
+        if let TypeKind::Double { .. } = ty2.kind {
+            return Type::type_double();
+        }
+        if ty1.is_flonum() || ty2.is_flonum() {
+            return Type::type_float();
+        }
+
+        let promote = |ty: &Type| match ty.size() {
+            Some(size) if size < 4 => Type::type_int(),
+            _ => ty.unqualified(),
+        };
+        let ty1 = promote(ty1);
+        let ty2 = promote(ty2);
+
+        if ty1.size() != ty2.size() {
+            return if ty1.size() < ty2.size() { ty2 } else { ty1 };
+        }
+        if ty2.is_unsigned {
+            return ty2;
+        }
+        ty1
+    }
+
+    /// Returns the members of a struct or union type.
+    pub fn members(&self) -> Option<&Vec<Member>> {
         match &self.kind {
-            TypeKind::Int { size }
-            | TypeKind::Ptr { size, .. }
-            | TypeKind::Array { size, .. }
-            | TypeKind::Char { size, .. }
-            | TypeKind::Struct { size, .. } => Some(size.clone()),
+            TypeKind::Struct { body, .. } | TypeKind::Union { body, .. } => {
+                body.get().map(|body| &body.members)
+            }
             _ => None,
         }
     }
 
+    /// Returns the alignment of the type in bytes. Scalars are aligned to
+    /// their size, arrays like their elements and structs and unions like
+    /// their most strictly aligned member.
+    pub fn align(&self) -> u64 {
+        match &self.kind {
+            TypeKind::Array { base, .. } | TypeKind::Vla { base, .. } => base.align(),
+            TypeKind::Struct { .. } | TypeKind::Union { .. } => self
+                .members()
+                .into_iter()
+                .flatten()
+                .map(|member| member.align)
+                .max()
+                .unwrap_or(1),
+            _ => self.size().unwrap_or(1),
+        }
+    }
+
     pub fn pointer_to(self) -> Self {
         Self {
             name: None,
@@ -57,160 +464,587 @@ impl Type {
                 size: 8,
                 base: Box::new(self),
             },
+            is_unsigned: true,
+            qual: Qualifiers::default(),
         }
     }
 
-    pub fn func_type(&self, params: Vec<Type>) -> Self {
+    pub fn func_type(&self, params: Vec<Type>, is_variadic: bool, is_prototyped: bool) -> Self {
         Self {
             name: None,
             kind: TypeKind::Func {
-                params: Box::new(params),
+                params,
                 return_ty: Some(Box::new(self.clone())),
+                is_variadic,
+                is_prototyped,
             },
+            is_unsigned: false,
+            qual: Qualifiers::default(),
         }
     }
 
-    pub fn array_of(self, len: u16) -> Self {
-        match self.size() {
-            Some(size) => Self {
-                name: None,
-                kind: TypeKind::Array {
-                    base: Box::new(self),
-                    size: size * len,
-                    len,
-                },
+    pub fn array_of(self, len: u64) -> Self {
+        self.array_of_len(Some(len))
+    }
+
+    /// Returns a variable-length array of `len` elements of `self`, whose size
+    /// is stored in `size_var` at run time.
+    pub fn vla_of(self, len: Node, size_var: Var) -> Self {
+        Self {
+            name: None,
+            kind: TypeKind::Vla {
+                len: Box::new(len),
+                base: Box::new(self),
+                size_var: Box::new(size_var),
+            },
+            is_unsigned: false,
+            qual: Qualifiers::default(),
+        }
+    }
+
+    /// Returns an array of `self` whose length is not known yet.
+    pub fn incomplete_array_of(self) -> Self {
+        self.array_of_len(None)
+    }
+
+    fn array_of_len(self, len: Option<u64>) -> Self {
+        if self.size().is_none() && !self.is_vla() {
+            unreachable!("size does not exist");
+        }
+        Self {
+            name: None,
+            kind: TypeKind::Array {
+                base: Box::new(self),
+                len,
             },
-            None => unreachable!("size does not exist"),
+            is_unsigned: false,
+            qual: Qualifiers::default(),
         }
     }
 }
 
 impl Node {
-    fn add_type_to_body(&mut self) {
-        match &self.kind {
-            NodeKind::Block { body } => {
-                let mut body = body.clone();
-                for node in body.iter_mut() {
-                    node.add_type();
+    fn add_type_to_children(&mut self) -> Result<(), TypeError> {
+        match &mut self.kind {
+            NodeKind::If { cond, then, els } => {
+                cond.add_type()?;
+                cond.check_cond()?;
+                then.add_type()?;
+                if let Some(els) = els {
+                    els.add_type()?;
+                }
+            }
+            NodeKind::While { cond, then } => {
+                cond.add_type()?;
+                cond.check_cond()?;
+                then.add_type()?;
+            }
+            NodeKind::For {
+                init,
+                inc,
+                cond,
+                then,
+            } => {
+                init.add_type()?;
+                if let Some(inc) = inc {
+                    inc.add_type()?;
+                }
+                if let Some(cond) = cond {
+                    cond.add_type()?;
+                    cond.check_cond()?;
                 }
-                self.kind = NodeKind::Block { body };
+                then.add_type()?;
             }
-            NodeKind::StmtExpr { body } => {
-                let mut body = body.clone();
+            NodeKind::Block { body, .. } | NodeKind::StmtExpr { body, .. } => {
                 for node in body.iter_mut() {
-                    node.add_type();
+                    node.add_type()?;
+                }
+            }
+            NodeKind::FuncCall { args, .. } => {
+                for arg in args.iter_mut() {
+                    arg.add_type()?;
+                    arg.check_not_void()?;
                 }
-                self.kind = NodeKind::StmtExpr { body };
             }
             _ => (),
         }
+        Ok(())
     }
 
-    fn add_type_to_args(&mut self) {
+    /// Returns true if the expression designates an object, so that it can be
+    /// assigned to or have its address taken. Casts and other values computed
+    /// into a register are not lvalues.
+    fn is_lvalue(&self) -> bool {
         match &self.kind {
-            NodeKind::FuncCall { name, args } => {
-                let mut args = args.clone();
-                for node in args.iter_mut() {
-                    node.add_type();
-                }
-                self.kind = NodeKind::FuncCall {
-                    name: name.clone(),
-                    args,
-                };
+            NodeKind::Var(_) | NodeKind::Deref => true,
+            NodeKind::Member(_) => self.lhs.as_ref().is_some_and(|lhs| lhs.is_lvalue()),
+            NodeKind::Comma => self.rhs.as_ref().is_some_and(|rhs| rhs.is_lvalue()),
+            _ => false,
+        }
+    }
+
+    /// Returns true for a null pointer constant: the integer constant 0,
+    /// possibly cast to an integer type.
+    pub fn is_null_pointer_constant(&self) -> bool {
+        match &self.kind {
+            NodeKind::Num(0) => true,
+            NodeKind::Cast => {
+                self.ty.as_ref().is_some_and(|ty| ty.is_integer())
+                    && self
+                        .lhs
+                        .as_ref()
+                        .is_some_and(|lhs| lhs.is_null_pointer_constant())
             }
-            _ => (),
+            _ => false,
         }
     }
 
-    pub fn add_type(&mut self) {
+    /// Rejects expressions of type `void` used where a value is required.
+    pub fn check_not_void(&self) -> Result<(), TypeError> {
+        match &self.ty {
+            Some(ty) if ty.is_void() => Err(TypeError::new(
+                &self.token,
+                "void value not ignored as it ought to be",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects controlling expressions of `if`, `while` and `for` that are not
+    /// scalars.
+    fn check_cond(&self) -> Result<(), TypeError> {
+        self.check_not_void()?;
+        match &self.ty {
+            Some(ty) if ty.is_struct_or_union() => Err(TypeError::new(
+                &self.token,
+                format!(
+                    "used {} type value where scalar is required",
+                    if matches!(ty.kind, TypeKind::Union { .. }) {
+                        "union"
+                    } else {
+                        "struct"
+                    }
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Converts both operands to their common type by inserting casts, as done
+    /// by the usual arithmetic conversions. Both operands must be scalars, and
+    /// `arithmetic_only` additionally rejects pointers.
+    fn usual_arith_conv(&mut self, arithmetic_only: bool) -> Result<(), TypeError> {
+        let (lhs_ty, rhs_ty) = match (
+            self.lhs.as_ref().and_then(|lhs| lhs.ty.as_ref()),
+            self.rhs.as_ref().and_then(|rhs| rhs.ty.as_ref()),
+        ) {
+            (Some(lhs_ty), Some(rhs_ty)) => (lhs_ty, rhs_ty),
+            _ => return Ok(()),
+        };
+        let is_operand = |ty: &Type| {
+            ty.is_numeric()
+                || !arithmetic_only && (ty.is_pointer() || matches!(ty.kind, TypeKind::Func { .. }))
+        };
+        if !is_operand(lhs_ty) || !is_operand(rhs_ty) {
+            return Err(TypeError::new(&self.token, "invalid operands"));
+        }
+        // A pointer may only be compared with another pointer or an integer.
+        if lhs_ty.is_pointer() && rhs_ty.is_flonum() || lhs_ty.is_flonum() && rhs_ty.is_pointer() {
+            return Err(TypeError::new(&self.token, "invalid operands"));
+        }
+
+        let ty = Type::common_type(lhs_ty, rhs_ty);
+        if let (Some(lhs), Some(rhs)) = (self.lhs.take(), self.rhs.take()) {
+            self.lhs = Some(Box::new(Node::new_cast(*lhs, ty.clone())?));
+            self.rhs = Some(Box::new(Node::new_cast(*rhs, ty)?));
+        }
+        Ok(())
+    }
+
+    pub fn add_type(&mut self) -> Result<(), TypeError> {
         if self.ty.is_some() {
-            return;
+            return Ok(());
         }
 
         if let Some(lhs) = self.lhs.as_mut() {
-            lhs.add_type();
+            lhs.add_type()?;
         }
         if let Some(rhs) = self.rhs.as_mut() {
-            rhs.add_type();
+            rhs.add_type()?;
         }
 
-        self.add_type_to_body();
-        self.add_type_to_args();
+        self.add_type_to_children()?;
 
-        if let NodeKind::FuncCall { args, .. } = &mut self.kind {
-            for arg in args.iter_mut() {
-                arg.add_type();
+        match &self.kind {
+            NodeKind::Add
+            | NodeKind::Sub
+            | NodeKind::Mul
+            | NodeKind::Div
+            | NodeKind::Eq
+            | NodeKind::Ne
+            | NodeKind::Lt
+            | NodeKind::Le
+            | NodeKind::Assign => {
+                for operand in [self.lhs.as_ref(), self.rhs.as_ref()].iter().flatten() {
+                    operand.check_not_void()?;
+                }
             }
+            NodeKind::Neg | NodeKind::Deref => {
+                if let Some(lhs) = &self.lhs {
+                    lhs.check_not_void()?;
+                }
+            }
+            _ => (),
         }
 
         match &self.kind {
-            NodeKind::Add | NodeKind::Sub | NodeKind::Mul | NodeKind::Div => {
-                self.ty = self.lhs.as_ref().map(|lhs| lhs.ty.clone()).flatten()
+            NodeKind::Add | NodeKind::Sub => {
+                self.usual_arith_conv(false)?;
+                self.ty = self.lhs.as_ref().and_then(|lhs| lhs.ty.clone())
+            }
+            NodeKind::Mul | NodeKind::Div => {
+                self.usual_arith_conv(true)?;
+                self.ty = self.lhs.as_ref().and_then(|lhs| lhs.ty.clone())
+            }
+            NodeKind::Neg => {
+                if let Some(lhs) = self.lhs.take() {
+                    let ty = lhs.ty.as_ref().unwrap();
+                    if !ty.is_numeric() {
+                        return Err(TypeError::new(
+                            &self.token,
+                            "wrong type argument to unary minus",
+                        ));
+                    }
+                    // The operand undergoes the integer promotions.
+                    let ty = Type::common_type(ty, &Type::type_int());
+                    self.lhs = Some(Box::new(Node::new_cast(*lhs, ty.clone())?));
+                    self.ty = Some(ty);
+                }
             }
             NodeKind::Assign => {
                 if let Some(lhs) = &self.lhs {
+                    let is_func = lhs
+                        .ty
+                        .as_ref()
+                        .is_some_and(|ty| matches!(ty.kind, TypeKind::Func { .. }));
+                    if !lhs.is_lvalue() || is_func {
+                        return Err(TypeError::new(
+                            &lhs.token,
+                            "lvalue required as left operand of assignment",
+                        ));
+                    }
                     if let Some(ty) = &lhs.ty {
-                        if let TypeKind::Array { .. } = ty.kind {
-                            panic!("not an lvalue");
+                        if let TypeKind::Array { .. } | TypeKind::Vla { .. } = ty.kind {
+                            return Err(TypeError::new(&lhs.token, "not an lvalue"));
+                        }
+                        if ty.qual.is_const {
+                            let msg = match &lhs.kind {
+                                NodeKind::Var(var) => {
+                                    format!("assignment of read-only variable '{}'", var.name)
+                                }
+                                NodeKind::Member(Member {
+                                    name: Some(name), ..
+                                }) => format!("assignment of read-only member '{}'", name.str),
+                                _ => String::from("assignment of read-only location"),
+                            };
+                            return Err(TypeError::new(&lhs.token, msg));
                         }
                     }
                 }
                 log::debug!("add type to assign");
-                self.ty = self.lhs.as_ref().map(|lhs| lhs.ty.clone()).flatten()
+                self.ty = self.lhs.as_ref().and_then(|lhs| lhs.ty.clone());
+                if let (Some(ty), Some(rhs)) = (&self.ty, self.rhs.take()) {
+                    let rhs_ty = rhs.ty.as_ref().unwrap();
+                    // A function designator converts to a pointer to the
+                    // function.
+                    let rhs_is_pointer =
+                        rhs_ty.is_pointer() || matches!(rhs_ty.kind, TypeKind::Func { .. });
+                    let is_aggregate = ty.is_struct_or_union() || rhs_ty.is_struct_or_union();
+                    // A pointer cannot be converted to or from a floating
+                    // type, even with a cast.
+                    let is_flonum_ptr =
+                        ty.is_flonum() && rhs_is_pointer || ty.is_pointer() && rhs_ty.is_flonum();
+                    self.rhs = Some(Box::new(if is_aggregate || is_flonum_ptr {
+                        if is_flonum_ptr || !ty.is_compatible(rhs_ty) {
+                            return Err(TypeError::new(
+                                &self.token,
+                                format!(
+                                    "incompatible types when assigning to type '{}' from type '{}'",
+                                    ty, rhs_ty
+                                ),
+                            ));
+                        }
+                        *rhs
+                    } else {
+                        // Only the null pointer constant converts between
+                        // integers and pointers implicitly.
+                        let is_null = rhs.is_null_pointer_constant();
+                        let mismatch = if matches!(ty.kind, TypeKind::Ptr { .. })
+                            && rhs_ty.is_integer()
+                            && !is_null
+                        {
+                            Some("makes pointer from integer without a cast")
+                        } else if ty.is_integer() && !ty.is_bool() && rhs_is_pointer {
+                            Some("makes integer from pointer without a cast")
+                        } else {
+                            None
+                        };
+                        if let Some(msg) = mismatch {
+                            return Err(TypeError::new(
+                                &self.token,
+                                format!("assignment to '{}' from '{}' {}", ty, rhs_ty, msg),
+                            ));
+                        }
+                        Node::new_cast(*rhs, ty.clone())?
+                    }));
+                }
+            }
+            NodeKind::Eq | NodeKind::Ne | NodeKind::Lt | NodeKind::Le => {
+                self.usual_arith_conv(false)?;
+                self.ty = Some(Type::type_int())
+            }
+            NodeKind::Num(_) => self.ty = Some(Type::type_int()),
+            NodeKind::FuncCall { func_ty, .. } => {
+                self.ty = match func_ty.as_ref().map(|ty| &ty.kind) {
+                    Some(TypeKind::Func {
+                        return_ty: Some(return_ty),
+                        ..
+                    }) => Some(*return_ty.clone()),
+                    _ => Some(Type::type_int()),
+                }
             }
-            NodeKind::Eq
-            | NodeKind::Ne
-            | NodeKind::Lt
-            | NodeKind::Le
-            | NodeKind::Num(_)
-            | NodeKind::FuncCall { .. } => self.ty = Some(Type::type_int()),
             NodeKind::Var(var) => self.ty = Some(var.ty.clone()),
             NodeKind::Comma => {
                 if let Some(rhs) = &self.rhs {
                     self.ty = rhs.ty.clone()
                 }
             }
-            NodeKind::Member(member) => self.ty = Some(member.ty.clone()),
+            NodeKind::Member(member) => {
+                // A member of a const or volatile struct is const or volatile
+                // too.
+                let mut ty = member.ty.clone();
+                // A bit-field that fits in an int is read as an int, as the
+                // integer promotions would convert it anyway.
+                if member.is_bitfield
+                    && ty.size().is_some_and(|size| size <= 4)
+                    && !matches!(ty.kind, TypeKind::Bool { .. })
+                    && (member.bit_width < 32 || member.bit_width == 32 && !ty.is_unsigned)
+                {
+                    let qual = ty.qual;
+                    ty = Type::type_int();
+                    ty.qual = qual;
+                }
+                if let Some(lhs_ty) = self.lhs.as_ref().and_then(|lhs| lhs.ty.as_ref()) {
+                    ty.qual.is_const |= lhs_ty.qual.is_const;
+                    ty.qual.is_volatile |= lhs_ty.qual.is_volatile;
+                }
+                self.ty = Some(ty);
+            }
             NodeKind::Addr => {
-                self.ty = if let Some(TypeKind::Array { base, .. }) = self
-                    .lhs
-                    .as_ref()
-                    .map(|lhs| lhs.ty.as_ref())
-                    .flatten()
-                    .map(|ty| ty.clone().kind)
+                if let Some(lhs) = self.lhs.as_ref().filter(|lhs| !lhs.is_lvalue()) {
+                    return Err(TypeError::new(
+                        &lhs.token,
+                        "lvalue required as unary '&' operand",
+                    ));
+                }
+                if let Some(NodeKind::Member(member)) = self.lhs.as_ref().map(|lhs| &lhs.kind) {
+                    if member.is_bitfield {
+                        return Err(TypeError::new(
+                            &self.token,
+                            "cannot take address of bit-field",
+                        ));
+                    }
+                }
+                self.ty = if let Some(TypeKind::Array { base, .. } | TypeKind::Vla { base, .. }) =
+                    self.lhs
+                        .as_ref()
+                        .and_then(|lhs| lhs.ty.as_ref())
+                        .map(|ty| ty.clone().kind)
                 {
                     Some(base.pointer_to())
                 } else {
                     self.lhs
                         .as_ref()
-                        .map(|lhs| lhs.ty.clone().map(|ty| ty.pointer_to()))
-                        .flatten()
+                        .and_then(|lhs| lhs.ty.clone().map(|ty| ty.pointer_to()))
                 };
             }
             NodeKind::Deref => {
-                if let Some(Some(base)) = self
+                // A function designator decays to a pointer to the function,
+                // so dereferencing it yields the function again.
+                if let Some(ty) = self.lhs.as_ref().and_then(|lhs| lhs.ty.as_ref()) {
+                    if let TypeKind::Func { .. } = ty.kind {
+                        self.ty = Some(ty.clone());
+                        return Ok(());
+                    }
+                }
+                match self
                     .lhs
                     .as_ref()
-                    .map(|lhs| lhs.ty.clone().map(|ty| ty.base()))
-                    .flatten()
+                    .and_then(|lhs| lhs.ty.as_ref().and_then(|ty| ty.base()))
                 {
-                    self.ty = Some(base);
-                    return;
+                    Some(base) if base.is_void() => {
+                        return Err(TypeError::new(
+                            &self.token,
+                            "dereferencing a 'void *' pointer",
+                        ))
+                    }
+                    Some(base) => self.ty = Some(base),
+                    None => return Err(TypeError::new(&self.token, "invalid pointer dereference")),
                 }
-                unreachable!("invalid pointer dereference")
             }
-            NodeKind::StmtExpr { body } => {
-                if let Some(stmt) = body.last() {
-                    log::debug!("stmt={:?}", stmt);
-                    self.ty = Some(stmt.ty.clone().unwrap());
-                    return;
-                }
-
-                // unreachable!("statement expression returning void is not supported",)
+            NodeKind::StmtExpr { body, .. } => {
+                self.ty = match body.last() {
+                    Some(stmt) if stmt.ty.is_some() => stmt.ty.clone(),
+                    _ => Some(Type::type_void()),
+                };
             }
             _ => {}
         }
 
         log::debug!("type={:?}", self.ty);
+        Ok(())
+    }
+}
+
+/// Formats a type the way it is spelled in C, e.g. `int (*)(char *)`.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.declaration(String::new()))
+    }
+}
+
+impl Type {
+    /// Returns the C declaration of `declarator` with this type, where
+    /// `declarator` is what has already been built around the name.
+    fn declaration(&self, declarator: String) -> String {
+        let qual = self.qual.to_string();
+        let sign = if self.is_unsigned { "unsigned " } else { "" };
+        let specifier = match &self.kind {
+            TypeKind::Void => String::from("void"),
+            TypeKind::Bool { .. } => String::from("_Bool"),
+            TypeKind::Char { .. } => format!("{}char", sign),
+            TypeKind::Short { .. } => format!("{}short", sign),
+            TypeKind::Int { .. } => format!("{}int", sign),
+            TypeKind::Long { .. } => format!("{}long", sign),
+            TypeKind::Enum { .. } => String::from("enum"),
+            TypeKind::Float { .. } => String::from("float"),
+            TypeKind::Double { .. } => String::from("double"),
+            TypeKind::LongDouble { .. } => String::from("long double"),
+            TypeKind::Struct { tag, .. } => {
+                format!("struct {}", tag.as_deref().unwrap_or("<anonymous>"))
+            }
+            TypeKind::Union { tag, .. } => {
+                format!("union {}", tag.as_deref().unwrap_or("<anonymous>"))
+            }
+            TypeKind::Ptr { base, .. } => {
+                let declarator = match (qual.is_empty(), declarator.is_empty()) {
+                    (true, _) => format!("*{}", declarator),
+                    (false, true) => format!("*{}", qual),
+                    (false, false) => format!("*{} {}", qual, declarator),
+                };
+                return match base.kind {
+                    TypeKind::Array { .. } | TypeKind::Vla { .. } | TypeKind::Func { .. } => {
+                        base.declaration(format!("({})", declarator))
+                    }
+                    _ => base.declaration(declarator),
+                };
+            }
+            TypeKind::Array { base, len } => {
+                let len = len.map(|len| len.to_string()).unwrap_or_default();
+                return base.declaration(format!("{}[{}]", declarator, len));
+            }
+            TypeKind::Vla { base, .. } => {
+                return base.declaration(format!("{}[*]", declarator));
+            }
+            TypeKind::Func {
+                params,
+                return_ty,
+                is_variadic,
+                is_prototyped,
+            } => {
+                let mut params: Vec<String> = params.iter().map(|ty| ty.to_string()).collect();
+                if *is_variadic {
+                    params.push(String::from("..."));
+                }
+                if params.is_empty() && *is_prototyped {
+                    params.push(String::from("void"));
+                }
+                let declarator = format!("{}({})", declarator, params.join(", "));
+                return match return_ty {
+                    Some(return_ty) => return_ty.declaration(declarator),
+                    None => declarator,
+                };
+            }
+        };
+
+        let specifier = match qual.is_empty() {
+            true => specifier,
+            false => format!("{} {}", qual, specifier),
+        };
+        if declarator.is_empty() || declarator.starts_with('[') {
+            format!("{}{}", specifier, declarator)
+        } else {
+            format!("{} {}", specifier, declarator)
+        }
+    }
+}
+
+impl Qualifiers {
+    /// Returns the qualifiers in `self` that `other` lacks.
+    pub fn missing_from(self, other: Qualifiers) -> Qualifiers {
+        Qualifiers {
+            is_const: self.is_const && !other.is_const,
+            is_volatile: self.is_volatile && !other.is_volatile,
+            is_restrict: self.is_restrict && !other.is_restrict,
+        }
+    }
+
+    /// Returns the names of the qualifiers, as written in C.
+    pub fn names(self) -> Vec<&'static str> {
+        [
+            (self.is_const, "const"),
+            (self.is_volatile, "volatile"),
+            (self.is_restrict, "restrict"),
+        ]
+        .iter()
+        .filter(|(is_set, _)| *is_set)
+        .map(|(_, name)| *name)
+        .collect()
+    }
+}
+
+impl fmt::Display for Qualifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.names().join(" "))
+    }
+}
+
+/// Struct members are printed without their types, which may refer back to
+/// the struct itself.
+impl fmt::Debug for StructBody {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let members: Vec<(Option<&str>, u64)> = self
+            .members
+            .iter()
+            .map(|member| {
+                (
+                    member.name.as_ref().map(|name| name.str.as_str()),
+                    member.offset,
+                )
+            })
+            .collect();
+        f.debug_struct("StructBody")
+            .field("members", &members)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl TypeError {
+    pub fn new(token: &Token, msg: impl Into<String>) -> Self {
+        TypeError {
+            token: token.clone(),
+            msg: msg.into(),
+        }
     }
 }
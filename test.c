@@ -6,8 +6,147 @@
  * This is a block comment.
  */
 
+int printf(char *fmt, ...);
+void exit(int status);
+
+typedef int MyInt, MyInt2[4];
+enum color { RED, GREEN = 5, BLUE };
+struct point;
+int point_sum(struct point *p);
+struct point { int x; int y; };
+
 int g1;
 int g2[4];
+_Alignas(32) char g_aligned;
+char g_align_char;
+short g_align_short;
+long g_align_long;
+
+int add_later(int x, int);
+int add_later(int, int y);
+char *str_later(void);
+long long_later(void);
+int sub_unproto();
+double half_unproto();
+
+typedef struct { int quot; int rem; } div_t;
+typedef struct { long quot; long rem; } ldiv_t;
+div_t div(int numer, int denom);
+ldiv_t ldiv(long numer, long denom);
+
+typedef struct { char a; } S1;
+typedef struct { char a, b, c; } S3;
+typedef struct { int a; char b; short c; } S8;
+typedef struct { int a, b, c; } S12;
+typedef struct { long a; char b; } S16;
+typedef struct { long a, b, c; } S24;
+typedef struct { unsigned a:3; int b:5; char c; long d:40; unsigned e:1; } BF;
+int gcc_s1(S1 x);
+int gcc_s3(S3 x);
+long gcc_s8(S8 x);
+long gcc_s12(S12 x);
+long gcc_s16(S16 x);
+long gcc_s24(S24 x);
+long gcc_spill(long a, long b, long c, long d, long e, S16 x, long f);
+long gcc_many(long a, long b, long c, long d, long e, long f, long g, S3 x, long h);
+S1 gcc_ret_s1(void);
+S3 gcc_ret_s3(void);
+S12 gcc_ret_s12(void);
+S16 gcc_ret_s16(void);
+S24 gcc_ret_s24(long n);
+long gcc_call_qcc(void);
+long gcc_call_qcc_bool(void);
+long gcc_call_qcc_args(void);
+void gcc_bf_fill(BF *p);
+long gcc_bf_check(BF *p);
+typedef struct { double a; } SD;
+typedef struct { float a, b; } SFF;
+typedef struct { float a; int b; double c; } SFID;
+typedef struct { double a; long b; } SDL;
+double gcc_fadd(double a, float b, int c);
+float gcc_fmul(float a, float b);
+double gcc_fmany(double a, double b, double c, double d, double e, double f, double g,
+                 double h, double i, float j);
+double gcc_fmix(int a, double b, long c, float d, char e, double f);
+double gcc_sd(SD x);
+double gcc_sff(SFF x);
+double gcc_sfid(SFID x);
+double gcc_sdl(SDL x);
+SFF gcc_ret_sff(void);
+SFID gcc_ret_sfid(void);
+SDL gcc_ret_sdl(void);
+double gcc_vsum(int n, ...);
+double gcc_call_qcc_float(void);
+int sprintf(char *buf, char *fmt, ...);
+int strcmp(char *a, char *b);
+double fdiv(double a, double b);
+long double ld_half(long double x);
+float fneg(float x);
+double qcc_fmix(int a, double b, long c, float d, char e, double f);
+SFID qcc_ret_sfid(void);
+double qcc_sfid(SFID x);
+typedef struct { long double a; } SLD;
+typedef struct { char a; long double b; } SCLD;
+long double gcc_ld_add(long double a, double b, long double c);
+long double gcc_ld_many(int a, long double b, double c, int d, long double e, float f);
+long double gcc_sld(SLD x);
+long double gcc_scld(SCLD x);
+SLD gcc_ret_sld(void);
+SCLD gcc_ret_scld(void);
+long double gcc_vsum_ld(int n, ...);
+long double gcc_call_qcc_ld(void);
+long double gcc_ld_tenth(void);
+long double gcc_ld_tiny(void);
+long double gcc_ld_huge(void);
+long double qcc_ld_many(int a, long double b, double c, int d, long double e, float f);
+SLD qcc_ret_sld(void);
+long double qcc_scld(SCLD x);
+long double g_ldouble = 1.5L * 4;
+long double g_ldoubles[] = {0.5, 1, 2.5L};
+int g_from_ldouble = 2.9L;
+long double g_ld_tenth = 0.1L;
+long double g_ld_neg_tenth = -0.1L;
+double g_double = 1.5 * 4;
+float g_floats[] = {0.5, 1, 2.5f};
+double g_neg_zero = -0.0;
+int g_from_double = 2.9;
+S24 qcc_ret_s24(long n);
+long qcc_s24(S24 x);
+long qcc_spill(long a, long b, long c, long d, long e, S16 x, long f);
+S12 y_of(S12 x);
+
+void qsort(void *base, long nmemb, long size, int (*compar)(void *, void *));
+void qsort(void *base, long nmemb, long size, int compar(void *, void *));
+int twice(int x);
+int apply(int (*f)(int), int x);
+int apply(int (*)(int), int);
+int apply_fn(int f(int), int x);
+int int_cmp(void *a, void *b);
+int (*g_fp)(int);
+struct ops { int (*unary)(int); int (*binary)(int, int); };
+int g_rows[2][4];
+int (*row_of(int n))[4];
+char *(*g_table[8])(void);
+int (*pick_op(int n))(int, int);
+long copy_sum(int *restrict dst, const int *restrict src, int n);
+const int g_const;
+volatile int g_volatile;
+extern int gcc_tbl[];
+int g_init[] = {1, 2, 3};
+char g_str[] = "hello";
+int g_grid[2][3] = {{1, 2, 3}, {4}};
+struct { char a; int b; unsigned c:4; long d; } g_struct = {1, -2, 15, 3};
+long g_long = -7;
+const int g_const_init = 9;
+int arr_sum(int a[], int n);
+int arr_sum(int a[3], int n);
+int arr_sum(int *a, int n);
+long stack_probe(void);
+int vla_fill(int n);
+long aligned_local(long a, long b, long c, long d, long e, long f, long g, S16 x);
+int vla_param_elem(int n, int a[n]);
+int vla_param_row(int n, int m, int a[n][m]);
+long vla_param_rows(int n, long a[][n]);
 
 int assert(int expected, int actual, char *code)
 {
@@ -57,6 +196,41 @@ int sub_char(char a, char b, char c)
     return a - b - c;
 }
 
+short sub_short(short a, short b, short c)
+{
+    return a - b - c;
+}
+
+long sub_long(long a, long b, long c)
+{
+    return a - b - c;
+}
+
+void ret_none(void)
+{
+    return;
+}
+
+void set_to_5(int *x)
+{
+    *x = 5;
+}
+
+_Bool bool_not(_Bool x)
+{
+    return x == 0;
+}
+
+_Bool long_to_bool(long x)
+{
+    return x;
+}
+
+long bool_stack(long a, long b, long c, long d, long e, long f, _Bool g)
+{
+    return g + 5;
+}
+
 int fib(int x)
 {
     if (x <= 1)
@@ -168,17 +342,17 @@ int main()
     assert(4, ({ int x[2][3]; int *y=x; y[4]=4; x[1][1]; }), "int x[2][3]; int *y=x; y[4]=4; x[1][1];");
     assert(5, ({ int x[2][3]; int *y=x; y[5]=5; x[1][2]; }), "int x[2][3]; int *y=x; y[5]=5; x[1][2];");
 
-    assert(8, ({ int x; sizeof(x); }), "int x; sizeof(x);");
-    assert(8, ({ int x; sizeof x; }), "int x; sizeof x;");
+    assert(4, ({ int x; sizeof(x); }), "int x; sizeof(x);");
+    assert(4, ({ int x; sizeof x; }), "int x; sizeof x;");
     assert(8, ({ int *x; sizeof(x); }), "int *x; sizeof(x);");
-    assert(32, ({ int x[4]; sizeof(x); }), "int x[4]; sizeof(x);");
-    assert(96, ({ int x[3][4]; sizeof(x); }), "int x[3][4]; sizeof(x);");
-    assert(32, ({ int x[3][4]; sizeof(*x); }), "int x[3][4]; sizeof(*x);");
-    assert(8, ({ int x[3][4]; sizeof(**x); }), "int x[3][4]; sizeof(**x);");
-    assert(9, ({ int x[3][4]; sizeof(**x) + 1; }), "int x[3][4]; sizeof(**x) + 1;");
-    assert(9, ({ int x[3][4]; sizeof **x + 1; }), "int x[3][4]; sizeof **x + 1;");
-    assert(8, ({ int x[3][4]; sizeof(**x + 1); }), "int x[3][4]; sizeof(**x + 1);");
-    assert(8, ({ int x=1; sizeof(x=2); }), "int x=1; sizeof(x=2);");
+    assert(16, ({ int x[4]; sizeof(x); }), "int x[4]; sizeof(x);");
+    assert(48, ({ int x[3][4]; sizeof(x); }), "int x[3][4]; sizeof(x);");
+    assert(16, ({ int x[3][4]; sizeof(*x); }), "int x[3][4]; sizeof(*x);");
+    assert(4, ({ int x[3][4]; sizeof(**x); }), "int x[3][4]; sizeof(**x);");
+    assert(5, ({ int x[3][4]; sizeof(**x) + 1; }), "int x[3][4]; sizeof(**x) + 1;");
+    assert(5, ({ int x[3][4]; sizeof **x + 1; }), "int x[3][4]; sizeof **x + 1;");
+    assert(4, ({ int x[3][4]; sizeof(**x + 1); }), "int x[3][4]; sizeof(**x + 1);");
+    assert(4, ({ int x=1; sizeof(x=2); }), "int x=1; sizeof(x=2);");
     assert(1, ({ int x=1; sizeof(x=2); x; }), "int x=1; sizeof(x=2); x;");
 
     assert(0, g1, "g1");
@@ -194,8 +368,8 @@ int main()
     assert(2, g2[2], "g2[2]");
     assert(3, g2[3], "g2[3]");
 
-    assert(8, sizeof(g1), "sizeof(g1)");
-    assert(32, sizeof(g2), "sizeof(g2)");
+    assert(4, sizeof(g1), "sizeof(g1)");
+    assert(16, sizeof(g2), "sizeof(g2)");
 
     assert(1, ({ char x=1; return x; }), "char x=1; return x;");
     assert(1, ({ char x=1; char y=2; return x; }), "char x=1; char y=2; return x;");
@@ -239,8 +413,8 @@ int main()
 
     assert(0, ({ "\x00"[0]; }), "\"\x00\"[0];");
     assert(119, ({ "\x77"[0]; }), "\"\x77\"[0];");
-    assert(165, ({ "\xA5"[0]; }), "\"\xA5\"[0];");
-    assert(255, ({ "\x00ff"[0]; }), "\"\x00ff\"[0];");
+    assert(-91, ({ "\xA5"[0]; }), "\"\xA5\"[0];");
+    assert(-1, ({ "\x00ff"[0]; }), "\"\x00ff\"[0];");
 
     assert(2, ({ int x=2; { int x=3; } x; }), "int x=2; { int x=3; } x;");
     assert(2, ({ int x=2; { int x=3; } int y=4; x; }), "int x=2; { int x=3; } int y=4; x;");
@@ -269,14 +443,763 @@ int main()
 
     assert(6, ({ struct { struct { int b; } a; } x; x.a.b=6; x.a.b; }), "struct { struct { int b; } a; } x; x.a.b=6; x.a.b;");
 
-    assert(8, ({ struct {int a;} x; sizeof(x); }), "struct {int a;} x; sizeof(x);");
-    assert(16, ({ struct {int a; int b;} x; sizeof(x); }), "struct {int a; int b;} x; sizeof(x);");
-    assert(24, ({ struct {int a[3];} x; sizeof(x); }), "struct {int a[3];} x; sizeof(x);");
-    assert(32, ({ struct {int a;} x[4]; sizeof(x); }), "struct {int a;} x[4]; sizeof(x);");
-    assert(48, ({ struct {int a[3];} x[2]; sizeof(x); }), "struct {int a[3];} x[2]; sizeof(x)};");
+    assert(4, ({ struct {int a;} x; sizeof(x); }), "struct {int a;} x; sizeof(x);");
+    assert(8, ({ struct {int a; int b;} x; sizeof(x); }), "struct {int a; int b;} x; sizeof(x);");
+    assert(12, ({ struct {int a[3];} x; sizeof(x); }), "struct {int a[3];} x; sizeof(x);");
+    assert(16, ({ struct {int a;} x[4]; sizeof(x); }), "struct {int a;} x[4]; sizeof(x);");
+    assert(24, ({ struct {int a[3];} x[2]; sizeof(x); }), "struct {int a[3];} x[2]; sizeof(x)};");
     assert(2, ({ struct {char a; char b;} x; sizeof(x); }), "struct {char a; char b;} x; sizeof(x);");
-    assert(9, ({ struct {char a; int b;} x; sizeof(x); }), "struct {char a; int b;} x; sizeof(x);");
+    assert(8, ({ struct {char a; int b;} x; sizeof(x); }), "struct {char a; int b;} x; sizeof(x);");
+
+    assert(1, ({ char x; sizeof(x); }), "char x; sizeof(x);");
+    assert(2, ({ short int x; sizeof(x); }), "short int x; sizeof(x);");
+    assert(2, ({ int short x; sizeof(x); }), "int short x; sizeof(x);");
+    assert(4, ({ int x; sizeof(x); }), "int x; sizeof(x);");
+    assert(8, ({ long int x; sizeof(x); }), "long int x; sizeof(x);");
+    assert(8, ({ int long x; sizeof(x); }), "int long x; sizeof(x);");
+    assert(8, ({ long long x; sizeof(x); }), "long long x; sizeof(x);");
+    assert(8, ({ long long int x; sizeof(x); }), "long long int x; sizeof(x);");
+    assert(1, ({ unsigned char x; sizeof(x); }), "unsigned char x; sizeof(x);");
+    assert(2, ({ unsigned short x; sizeof(x); }), "unsigned short x; sizeof(x);");
+    assert(4, ({ unsigned x; sizeof(x); }), "unsigned x; sizeof(x);");
+    assert(8, ({ unsigned long long x; sizeof(x); }), "unsigned long long x; sizeof(x);");
+    assert(4, ({ signed int x; sizeof(x); }), "signed int x; sizeof(x);");
+    assert(8, ({ int *x; sizeof(x); }), "int *x; sizeof(x);");
+
+    assert(1, ({ char x[2]; x[0]=1; x[1]=2; x[0]; }), "char x[2]; x[0]=1; x[1]=2; x[0];");
+    assert(1, ({ short x[2]; x[0]=1; x[1]=2; x[0]; }), "short x[2]; x[0]=1; x[1]=2; x[0];");
+    assert(2, ({ long x[2]; x[0]=1; x[1]=2; x[1]; }), "long x[2]; x[0]=1; x[1]=2; x[1];");
+    assert(0, ({ char c; short x; (long)&x - (long)&x / 2 * 2; }), "char c; short x; (long)&x - (long)&x / 2 * 2;");
+    assert(0, ({ char c; int x; (long)&x - (long)&x / 4 * 4; }), "char c; int x; (long)&x - (long)&x / 4 * 4;");
+    assert(0, ({ char c; short s; long x; (long)&x - (long)&x / 8 * 8; }), "char c; short s; long x; (long)&x - (long)&x / 8 * 8;");
+    assert(0, ({ (long)&g_align_short - (long)&g_align_short / 2 * 2; }), "(long)&g_align_short - (long)&g_align_short / 2 * 2;");
+    assert(0, ({ (long)&g_align_long - (long)&g_align_long / 8 * 8; }), "(long)&g_align_long - (long)&g_align_long / 8 * 8;");
+    assert(-1, ({ char x=255; x; }), "char x=255; x;");
+    assert(255, ({ unsigned char x=255; x; }), "unsigned char x=255; x;");
+    assert(-1, ({ short x=65535; x; }), "short x=65535; x;");
+    assert(65535, ({ unsigned short x=65535; x; }), "unsigned short x=65535; x;");
+    assert(44, ({ char x; x=300; }), "char x; x=300;");
+    assert(1, ({ int x=2147483647; x=x+1; x<0; }), "int x=2147483647; x=x+1; x<0;");
+    assert(1, ({ long x=2147483647; x=x+1; x>0; }), "long x=2147483647; x=x+1; x>0;");
+    assert(4, ({ sizeof(2147483647); }), "sizeof(2147483647);");
+    assert(8, ({ sizeof(2147483648); }), "sizeof(2147483648);");
+    assert(8, ({ sizeof(1L); }), "sizeof(1L);");
+    assert(8, ({ sizeof(1LL); }), "sizeof(1LL);");
+    assert(4, ({ sizeof(1U); }), "sizeof(1U);");
+    assert(8, ({ sizeof(1UL); }), "sizeof(1UL);");
+    assert(8, ({ int x; sizeof(sizeof(x)); }), "int x; sizeof(sizeof(x));");
+    assert(1, ({ long x=4294967296; x==4294967296; }), "long x=4294967296; x==4294967296;");
+    assert(0, ({ unsigned x=0; x-1<0; }), "unsigned x=0; x-1<0;");
+    assert(1, ({ int x=0; x-1<0; }), "int x=0; x-1<0;");
+    assert(2147483647, ({ unsigned x=4294967295; x/2; }), "unsigned x=4294967295; x/2;");
+    assert(-1, ({ int x=-2; x/2; }), "int x=-2; x/2;");
+    assert(1, ({ unsigned long x=1; x<18446744073709551615UL; }), "unsigned long x=1; x<18446744073709551615UL;");
+    assert(3, sub_short(7, 3, 1), "sub_short(7, 3, 1)");
+    assert(3, sub_long(7, 3, 1), "sub_long(7, 3, 1)");
+
+    ret_none();
+    assert(5, ({ int x=3; set_to_5(&x); x; }), "int x=3; set_to_5(&x); x;");
+    assert(3, ({ int x=3; void *p=&x; int *q=p; *q; }), "int x=3; void *p=&x; int *q=p; *q;");
+    assert(1, ({ char x[2]; void *p=x; char *q=p; q==x; }), "char x[2]; void *p=x; char *q=p; q==x;");
+    assert(8, ({ void *p; sizeof(p); }), "void *p; sizeof(p);");
+
+    assert(0, ({ _Bool x=0; x; }), "_Bool x=0; x;");
+    assert(1, ({ _Bool x=1; x; }), "_Bool x=1; x;");
+    assert(1, ({ _Bool x=2; x; }), "_Bool x=2; x;");
+    assert(1, ({ _Bool x=256; x; }), "_Bool x=256; x;");
+    assert(1, ({ _Bool x; x=-1; }), "_Bool x; x=-1;");
+    assert(1, ({ int y; _Bool x=&y; x; }), "int y; _Bool x=&y; x;");
+    assert(1, ({ typedef _Bool bool; bool x=3; x; }), "typedef _Bool bool; bool x=3; x;");
+    assert(3, ({ int bool=3; bool; }), "int bool=3; bool;");
+    assert(1, ({ _Bool x; sizeof(x); }), "_Bool x; sizeof(x);");
+    assert(2, ({ _Bool x=5; x+1; }), "_Bool x=5; x+1;");
+    assert(1, ({ struct {_Bool a; char b;} x; x.a=512; x.b=7; x.a; }), "struct {_Bool a; char b;} x; x.a=512; x.b=7; x.a;");
+    assert(0, bool_not(256), "bool_not(256)");
+    assert(1, bool_not(0), "bool_not(0)");
+    assert(1, long_to_bool(4294967296), "long_to_bool(4294967296)");
+    assert(0, long_to_bool(0), "long_to_bool(0)");
+    assert(15, gcc_call_qcc_bool(), "gcc_call_qcc_bool()");
+
+    assert(4, ({ char x; sizeof(x+1); }), "char x; sizeof(x+1);");
+    assert(4, ({ short x; sizeof(x*x); }), "short x; sizeof(x*x);");
+    assert(4, ({ _Bool x; sizeof(-x); }), "_Bool x; sizeof(-x);");
+    assert(8, ({ long x; sizeof(x+1); }), "long x; sizeof(x+1);");
+    assert(8, ({ int x; sizeof(x+1L); }), "int x; sizeof(x+1L);");
+    assert(4, ({ unsigned char x; sizeof(x==1); }), "unsigned char x; sizeof(x==1);");
+    assert(128, ({ char x=127; x+1; }), "char x=127; x+1;");
+    assert(256, ({ unsigned char x=255; x+1; }), "unsigned char x=255; x+1;");
+    assert(-256, ({ unsigned char x=255; short y=1; -x-y; }), "unsigned char x=255; short y=1; -x-y;");
+    assert(0, ({ int x=-1; unsigned y=1; x<y; }), "int x=-1; unsigned y=1; x<y;");
+    assert(1, ({ int x=-1; long y=1; x<y; }), "int x=-1; long y=1; x<y;");
+    assert(1, ({ unsigned x=1; long y=-1; x>y; }), "unsigned x=1; long y=-1; x>y;");
+    assert(0, ({ unsigned x=4294967295; x+1; }), "unsigned x=4294967295; x+1;");
+    assert(1, ({ int x=-1; unsigned long y=x; y==18446744073709551615UL; }), "int x=-1; unsigned long y=x; y==18446744073709551615UL;");
+    assert(2147483647, ({ int x=-2; unsigned y=2; x/y; }), "int x=-2; unsigned y=2; x/y;");
+
+    assert(131585, (int)8590066177, "(int)8590066177");
+    assert(513, (short)8590066177, "(short)8590066177");
+    assert(1, (char)8590066177, "(char)8590066177");
+    assert(1, (long)1, "(long)1");
+    assert(0, (long)&*(int *)0, "(long)&*(int *)0");
+    assert(513, ({ int x=512; *(char *)&x=1; x; }), "int x=512; *(char *)&x=1; x;");
+    assert(5, ({ int x=5; long y=(long)&x; *(int*)y; }), "int x=5; long y=(long)&x; *(int*)y;");
+    (void)1;
+    assert(-1, (char)255, "(char)255");
+    assert(-1, (signed char)255, "(signed char)255");
+    assert(255, (unsigned char)255, "(unsigned char)255");
+    assert(-1, (short)65535, "(short)65535");
+    assert(65535, (unsigned short)65535, "(unsigned short)65535");
+    assert(-1, (int)4294967295, "(int)4294967295");
+    assert(-1, (long)(int)4294967295, "(long)(int)4294967295");
+    assert(1, (unsigned long)(unsigned)-1 == 4294967295, "(unsigned long)(unsigned)-1 == 4294967295");
+    assert(1, (_Bool)2, "(_Bool)2");
+    assert(0, (_Bool)0, "(_Bool)0");
+    assert(1, (_Bool)(char)256 == 0, "(_Bool)(char)256 == 0");
+    assert(1, ({ int x; (_Bool)&x; }), "int x; (_Bool)&x;");
+    assert(4, sizeof((int)1L), "sizeof((int)1L)");
+    assert(-3, -(int)3, "-(int)3");
+
+    assert(7, add_later(3, 4), "add_later(3, 4)");
+    assert(98, str_later()[1], "str_later()[1]");
+    assert(8, sizeof(str_later()), "sizeof(str_later())");
+    assert(8, sizeof(long_later()), "sizeof(long_later())");
+    assert(1, long_later() == 4294967296, "long_later() == 4294967296");
+    assert(1, ({ int add_later(int, int); add_later(2, 3) == 5; }), "int add_later(int, int); add_later(2, 3) == 5;");
+    assert(4, sub_unproto(7, 3), "sub_unproto(7, 3)");
+    assert(-1, ({ char c=2; sub_unproto(1, c); }), "char c=2; sub_unproto(1, c);");
+    assert(1, half_unproto(2.5f) == 1.25, "half_unproto(2.5f) == 1.25");
+    assert(0, ({ char buf[16]; int snprintf(); snprintf(buf, 16, "%.1f %d", 2.5f, 3); strcmp(buf, "2.5 3"); }), "int snprintf(); snprintf(buf, 16, \"%.1f %d\", 2.5f, 3);");
+
+    assert(2, add_later(4294967297, 4294967297), "add_later(4294967297, 4294967297)");
+    assert(1, bool_not(0L), "bool_not(0L)");
+    assert(0, bool_not(&g1), "bool_not(&g1)");
+    assert(1, long_to_bool(1 == 1), "long_to_bool(1 == 1)");
+    assert(3, sub_char(263, 259, 1), "sub_char(263, 259, 1)");
+
+    assert(1, ({ typedef int t; t x=1; x; }), "typedef int t; t x=1; x;");
+    assert(1, ({ typedef struct {int a;} t; t x; x.a=1; x.a; }), "typedef struct {int a;} t; t x; x.a=1; x.a;");
+    assert(1, ({ typedef int t; t t=1; t; }), "typedef int t; t t=1; t;");
+    assert(2, ({ typedef struct {int a;} t; { typedef int t; } t x; x.a=2; x.a; }), "typedef struct {int a;} t; { typedef int t; } t x; x.a=2; x.a;");
+    assert(4, ({ typedef t; t x; sizeof(x); }), "typedef t; t x; sizeof(x);");
+    assert(3, ({ MyInt x=3; x; }), "MyInt x=3; x;");
+    assert(16, ({ MyInt2 x; sizeof(x); }), "MyInt2 x; sizeof(x);");
+    assert(5, ({ typedef int T; int y=5; T * x; x=&y; *x; }), "typedef int T; int y=5; T * x; x=&y; *x;");
+    assert(6, ({ typedef int T; int x=2, y; { int T=3; y = T * x; } y; }), "typedef int T; int x=2, y; { int T=3; y = T * x; } y;");
+    assert(8, ({ typedef long T; sizeof((T)1); }), "typedef long T; sizeof((T)1);");
+    assert(1, ({ int x=1; { int x=2; } x; }), "int x=1; { int x=2; } x;");
+    assert(2, ({ int x=1, y; { int x=2; y=x; } y; }), "int x=1, y; { int x=2; y=x; } y;");
+
+    assert(0, ({ enum { zero, one, two }; zero; }), "enum { zero, one, two }; zero;");
+    assert(1, ({ enum { zero, one, two }; one; }), "enum { zero, one, two }; one;");
+    assert(2, ({ enum { zero, one, two }; two; }), "enum { zero, one, two }; two;");
+    assert(5, ({ enum { five=5, six, seven }; five; }), "enum { five=5, six, seven }; five;");
+    assert(6, ({ enum { five=5, six, seven }; six; }), "enum { five=5, six, seven }; six;");
+    assert(0, ({ enum { zero, five=5, three=3, four }; zero; }), "enum { zero, five=5, three=3, four }; zero;");
+    assert(5, ({ enum { zero, five=5, three=3, four }; five; }), "enum { zero, five=5, three=3, four }; five;");
+    assert(3, ({ enum { zero, five=5, three=3, four }; three; }), "enum { zero, five=5, three=3, four }; three;");
+    assert(4, ({ enum { zero, five=5, three=3, four }; four; }), "enum { zero, five=5, three=3, four }; four;");
+    assert(4, ({ enum { zero, one, two } x; sizeof(x); }), "enum { zero, one, two } x; sizeof(x);");
+    assert(4, ({ enum t { zero, one, two }; enum t y; sizeof(y); }), "enum t { zero, one, two }; enum t y; sizeof(y);");
+    assert(1, ({ enum t { zero, one }; int r; { enum t { two=2 }; r=two; } r-one; }), "enum t { zero, one }; int r; { enum t { two=2 }; r=two; } r-one;");
+    assert(-1, ({ enum { minus=-1, }; minus; }), "enum { minus=-1, }; minus;");
+    assert(6, BLUE, "BLUE");
+    assert(11, ({ enum color c=BLUE; c+GREEN; }), "enum color c=BLUE; c+GREEN;");
+    assert(20, ({ enum { N=2+3 }; int a[N]; sizeof(a); }), "enum { N=2+3 }; int a[N]; sizeof(a);");
+    assert(24, ({ int a[2*3]; sizeof(a); }), "int a[2*3]; sizeof(a);");
+    assert(8, ({ int a[(char)258]; sizeof(a); }), "int a[(char)258]; sizeof(a);");
+    assert(4, ({ char b[3]; int a[sizeof(b)==3]; sizeof(a); }), "char b[3]; int a[sizeof(b)==3]; sizeof(a);");
+    assert(1, ({ enum { one=1 }; { int one=2; } one; }), "enum { one=1 }; { int one=2; } one;");
+
+    assert(8, ({ union { int a; char b[6]; } x; sizeof(x); }), "union { int a; char b[6]; } x; sizeof(x);");
+    assert(4, ({ union { int a; char b[4]; } x; sizeof(x); }), "union { int a; char b[4]; } x; sizeof(x);");
+    assert(16, ({ union { long a; char b[9]; } x; sizeof(x); }), "union { long a; char b[9]; } x; sizeof(x);");
+    assert(3, ({ union { char a[3]; } x; sizeof(x); }), "union { char a[3]; } x; sizeof(x);");
+    assert(3, ({ union { int a; char b[4]; } x; x.a = 515; x.b[0]; }), "union { int a; char b[4]; } x; x.a = 515; x.b[0];");
+    assert(2, ({ union { int a; char b[4]; } x; x.a = 515; x.b[1]; }), "union { int a; char b[4]; } x; x.a = 515; x.b[1];");
+    assert(0, ({ union { int a; char b[4]; } x; x.a = 515; x.b[2]; }), "union { int a; char b[4]; } x; x.a = 515; x.b[2];");
+    assert(1, ({ union { int a; short b; } x; x.b = 0; x.a = 65537; x.b; }), "union { int a; short b; } x; x.b = 0; x.a = 65537; x.b;");
+    assert(2, ({ struct { char c; union { int i; char d; } u; } s; s.u.i=258; s.u.d; }), "struct { char c; union { int i; char d; } u; } s; s.u.i=258; s.u.d;");
+
+    assert(8, ({ struct t {int a; int b;} x; struct t y; sizeof(y); }), "struct t {int a; int b;} x; struct t y; sizeof(y);");
+    assert(8, ({ struct t {int a; int b;}; struct t y; sizeof(y); }), "struct t {int a; int b;}; struct t y; sizeof(y);");
+    assert(2, ({ struct t {char a[2];}; { struct t {char a[4];}; } struct t y; sizeof(y); }), "struct t {char a[2];}; { struct t {char a[4];}; } struct t y; sizeof(y);");
+    assert(3, ({ struct t {int x;}; int t=1; struct t y; y.x=2; t+y.x; }), "struct t {int x;}; int t=1; struct t y; y.x=2; t+y.x;");
+    assert(2, ({ union u { int a; char b; }; union u x; x.a=258; x.b; }), "union u { int a; char b; }; union u x; x.a=258; x.b;");
+    assert(3, ({ struct node { int val; struct node *next; } a, b, c; a.val=1; b.val=2; c.val=3; a.next=&b; b.next=&c; c.next=0; (*(*a.next).next).val; }), "struct node { int val; struct node *next; } a, b, c; ...; (*(*a.next).next).val;");
+    assert(5, ({ struct s; struct s *p; struct s { int x; } v; p=&v; v.x=5; (*p).x; }), "struct s; struct s *p; struct s { int x; } v; p=&v; v.x=5; (*p).x;");
+    assert(7, ({ struct t { int a; } x; int n; { struct t; struct t { char c[3]; } y; n=sizeof(y); } n+sizeof(x); }), "struct t { int a; } x; int n; { struct t; struct t { char c[3]; } y; n=sizeof(y); } n+sizeof(x);");
+    assert(7, ({ struct point p; p.x=3; p.y=4; point_sum(&p); }), "struct point p; p.x=3; p.y=4; point_sum(&p);");
+    assert(16, ({ struct tree { struct tree *left; struct tree *right; } t; sizeof(t); }), "struct tree { struct tree *left; struct tree *right; } t; sizeof(t);");
+
+    assert(1, _Alignof(char), "_Alignof(char)");
+    assert(2, _Alignof(short), "_Alignof(short)");
+    assert(4, _Alignof(int), "_Alignof(int)");
+    assert(8, _Alignof(long), "_Alignof(long)");
+    assert(8, _Alignof(int *), "_Alignof(int *)");
+    assert(4, ({ int x[3]; _Alignof(x); }), "int x[3]; _Alignof(x);");
+    assert(8, ({ struct {char a; long b;} x; _Alignof(x); }), "struct {char a; long b;} x; _Alignof(x);");
+    assert(1, ({ struct {char a; char b[3];} x; _Alignof x; }), "struct {char a; char b[3];} x; _Alignof x;");
+    assert(16, ({ struct {char a; long b;} x; sizeof(x); }), "struct {char a; long b;} x; sizeof(x);");
+    assert(8, ({ struct {int a; char b;} x; sizeof(x); }), "struct {int a; char b;} x; sizeof(x);");
+    assert(12, ({ struct {char a; int b; char c;} x; sizeof(x); }), "struct {char a; int b; char c;} x; sizeof(x);");
+    assert(4, ({ struct {char a; int b;} x; (long)&x.b - (long)&x; }), "struct {char a; int b;} x; (long)&x.b - (long)&x;");
+    assert(24, ({ struct {char a; int b;} x[3]; sizeof(x); }), "struct {char a; int b;} x[3]; sizeof(x);");
+    assert(8, ({ union {char a[5]; int b;} x; sizeof(x); }), "union {char a[5]; int b;} x; sizeof(x);");
+    assert(0, ({ _Alignas(16) char x; (long)&x - (long)&x / 16 * 16; }), "_Alignas(16) char x; (long)&x - (long)&x / 16 * 16;");
+    assert(0, ({ char a; _Alignas(long) char x; (long)&x - (long)&x / 8 * 8; }), "char a; _Alignas(long) char x; (long)&x - (long)&x / 8 * 8;");
+    assert(0, ({ char a; _Alignas(32) char x; (long)&x - (long)&x / 32 * 32; }), "char a; _Alignas(32) char x; (long)&x - (long)&x / 32 * 32;");
+    assert(0, ({ _Alignas(64) char buf[3]; (long)buf - (long)buf / 64 * 64; }), "_Alignas(64) char buf[3]; (long)buf - (long)buf / 64 * 64;");
+    assert(4579, ({ S16 x; x.a=4; x.b=5; aligned_local(1, 2, 3, 4, 5, 6, 7, x); }), "S16 x; x.a=4; x.b=5; aligned_local(1, 2, 3, 4, 5, 6, 7, x);");
+    assert(0, (long)&g_aligned - (long)&g_aligned / 32 * 32, "(long)&g_aligned - (long)&g_aligned / 32 * 32");
+    assert(32, ({ struct {char a; _Alignas(16) char b;} x; sizeof(x); }), "struct {char a; _Alignas(16) char b;} x; sizeof(x);");
+    assert(16, ({ struct {char a; _Alignas(16) char b;} x; _Alignof(x); }), "struct {char a; _Alignas(16) char b;} x; _Alignof(x);");
+
+    assert(3, ({ struct {int a; int b;} x, *p=&x; p->a=3; x.a; }), "struct {int a; int b;} x, *p=&x; p->a=3; x.a;");
+    assert(7, ({ struct {char a; long b;} x, *p=&x; x.b=7; p->b; }), "struct {char a; long b;} x, *p=&x; x.b=7; p->b;");
+    assert(3, ({ struct node { int val; struct node *next; } a, b, c; a.val=1; b.val=2; c.val=3; a.next=&b; b.next=&c; c.next=0; a.next->next->val; }), "struct node { int val; struct node *next; } a, b, c; ...; a.next->next->val;");
+    assert(5, ({ struct {int a;} x[2]; x[1].a=5; (x+1)->a; }), "struct {int a;} x[2]; x[1].a=5; (x+1)->a;");
+    assert(4, ({ struct {int a;} x[2]; x->a=4; x[0].a; }), "struct {int a;} x[2]; x->a=4; x[0].a;");
+    assert(6, ({ union {int a; char b;} x, *p=&x; p->a=6; p->b; }), "union {int a; char b;} x, *p=&x; p->a=6; p->b;");
+    assert(2, ({ struct {int a[3];} x, *p=&x; p->a[1]=2; x.a[1]; }), "struct {int a[3];} x, *p=&x; p->a[1]=2; x.a[1];");
+
+    assert(3, ({ struct {int a; int b;} x, y; x.a=3; x.b=5; y=x; y.a; }), "struct {int a; int b;} x, y; x.a=3; x.b=5; y=x; y.a;");
+    assert(5, ({ struct {int a; int b;} x, y; x.a=3; x.b=5; y=x; y.b; }), "struct {int a; int b;} x, y; x.a=3; x.b=5; y=x; y.b;");
+    assert(7, ({ struct t {long a[3];} x, y; x.a[2]=7; y=x; x.a[2]=1; y.a[2]; }), "struct t {long a[3];} x, y; x.a[2]=7; y=x; x.a[2]=1; y.a[2];");
+    assert(4, ({ struct {char a[3]; char b;} x, y, *p=&y; x.b=4; *p=x; y.b; }), "struct {char a[3]; char b;} x, y, *p=&y; x.b=4; *p=x; y.b;");
+    assert(6, ({ union {int a; char b[6];} x, y; x.b[5]=6; y=x; y.b[5]; }), "union {int a; char b[6];} x, y; x.b[5]=6; y=x; y.b[5];");
+    assert(2, ({ struct {int a; int b;} x, y, z; x.a=2; z=y=x; z.a; }), "struct {int a; int b;} x, y, z; x.a=2; z=y=x; z.a;");
+    assert(3, ({ S12 x; x.c=3; (y_of(x)).c; }), "S12 x; x.c=3; (y_of(x)).c;");
+
+    assert(3, div(7, 2).quot, "div(7, 2).quot");
+    assert(1, div(7, 2).rem, "div(7, 2).rem");
+    assert(4, ({ ldiv_t q; q=ldiv(100000000000, 25000000000); q.quot; }), "ldiv_t q; q=ldiv(100000000000, 25000000000); q.quot;");
+    assert(3, ldiv(-7, 2).quot + 6, "ldiv(-7, 2).quot + 6");
+
+    assert(7, ({ S1 x; x.a=7; gcc_s1(x); }), "S1 x; x.a=7; gcc_s1(x);");
+    assert(123, ({ S3 x; x.a=1; x.b=2; x.c=3; gcc_s3(x); }), "S3 x; x.a=1; x.b=2; x.c=3; gcc_s3(x);");
+    assert(126, ({ S8 x; x.a=1; x.b=2; x.c=6; gcc_s8(x); }), "S8 x; x.a=1; x.b=2; x.c=6; gcc_s8(x);");
+    assert(456, ({ S12 x; x.a=4; x.b=5; x.c=6; gcc_s12(x); }), "S12 x; x.a=4; x.b=5; x.c=6; gcc_s12(x);");
+    assert(89, ({ S16 x; x.a=8; x.b=9; gcc_s16(x); }), "S16 x; x.a=8; x.b=9; gcc_s16(x);");
+    assert(789, ({ S24 x; x.a=7; x.b=8; x.c=9; gcc_s24(x); }), "S24 x; x.a=7; x.b=8; x.c=9; gcc_s24(x);");
+    assert(4575, ({ S16 x; x.a=4; x.b=5; gcc_spill(1, 2, 3, 4, 5, x, 6); }), "S16 x; x.a=4; x.b=5; gcc_spill(1, 2, 3, 4, 5, x, 6);");
+    assert(98391, ({ S3 x; x.a=3; x.c=8; gcc_many(1, 2, 3, 4, 5, 6, 7, x, 9); }), "S3 x; x.a=3; x.c=8; gcc_many(1, 2, 3, 4, 5, 6, 7, x, 9);");
+    assert(789, ({ S24 x; x.a=7; x.b=8; x.c=9; qcc_s24(x); }), "S24 x; x.a=7; x.b=8; x.c=9; qcc_s24(x);");
+    assert(4575, ({ S16 x; x.a=4; x.b=5; qcc_spill(1, 2, 3, 4, 5, x, 6); }), "S16 x; x.a=4; x.b=5; qcc_spill(1, 2, 3, 4, 5, x, 6);");
+
+    assert(7, gcc_ret_s1().a, "gcc_ret_s1().a");
+    assert(123, ({ S3 x; x=gcc_ret_s3(); x.a*100+x.b*10+x.c; }), "S3 x; x=gcc_ret_s3(); x.a*100+x.b*10+x.c;");
+    assert(456, ({ S12 x; x=gcc_ret_s12(); x.a*100+x.b*10+x.c; }), "S12 x; x=gcc_ret_s12(); x.a*100+x.b*10+x.c;");
+    assert(1, gcc_ret_s16().a == 123456789012, "gcc_ret_s16().a == 123456789012");
+    assert(9, gcc_ret_s16().b, "gcc_ret_s16().b");
+    assert(15, gcc_ret_s24(5).c, "gcc_ret_s24(5).c");
+    assert(6, ({ S24 x; x=gcc_ret_s24(1); x.a+x.b+x.c; }), "S24 x; x=gcc_ret_s24(1); x.a+x.b+x.c;");
+    assert(9, qcc_ret_s24(3).c, "qcc_ret_s24(3).c");
+    assert(33, gcc_call_qcc(), "gcc_call_qcc()");
+    assert(12304561, gcc_call_qcc_args(), "gcc_call_qcc_args()");
+
+    assert(6, ({ int (*fp)(int); fp=twice; fp(3); }), "int (*fp)(int); fp=twice; fp(3);");
+    assert(6, ({ int (*fp)(int); fp=&twice; (*fp)(3); }), "int (*fp)(int); fp=&twice; (*fp)(3);");
+    assert(6, ({ int (*fp)(int)=twice; (**fp)(3); }), "int (*fp)(int)=twice; (**fp)(3);");
+    assert(10, (*twice)(5), "(*twice)(5)");
+    assert(10, (&twice)(5), "(&twice)(5)");
+    assert(8, apply(twice, 4), "apply(twice, 4)");
+    assert(8, apply_fn(twice, 4), "apply_fn(twice, 4)");
+    assert(14, ({ g_fp=twice; g_fp(7); }), "g_fp=twice; g_fp(7);");
+    assert(7, ({ struct ops o; o.binary=add2; o.binary(3, 4); }), "struct ops o; o.binary=add2; o.binary(3, 4);");
+    assert(12, ({ struct ops o, *p=&o; p->unary=twice; p->unary(6); }), "struct ops o, *p=&o; p->unary=twice; p->unary(6);");
+    assert(9, ({ int (*ops[2])(int, int); ops[0]=add2; ops[1]=add2; ops[1](4, 5); }), "int (*ops[2])(int, int); ops[0]=add2; ops[1]=add2; ops[1](4, 5);");
+    assert(16, sizeof(({ int (*ops[2])(int, int); ops; })), "sizeof(({ int (*ops[2])(int, int); ops; }))");
+    assert(8, ({ int (*fp)(int); sizeof(fp); }), "int (*fp)(int); sizeof(fp);");
+    assert(1, ({ int (*fp)(int)=twice; fp==twice; }), "int (*fp)(int)=twice; fp==twice;");
+    assert(1, ({ int (*fp)(int)=twice; twice==fp; }), "int (*fp)(int)=twice; twice==fp;");
+    assert(15, ({ int (*fp)(int, int)=add2; S12 x; x.a=1; fp(fp(x.a, 2), add2(fp(3, 4), 5)); }), "int (*fp)(int, int)=add2; S12 x; x.a=1; fp(fp(x.a, 2), add2(fp(3, 4), 5));");
+    assert(1, ({ int a[5]; a[0]=5; a[1]=3; a[2]=4; a[3]=1; a[4]=2; qsort(a, 5, sizeof(a[0]), int_cmp); a[0]; }), "int a[5]; ...; qsort(a, 5, sizeof(a[0]), int_cmp); a[0];");
+    assert(5, ({ int a[5]; a[0]=5; a[1]=3; a[2]=4; a[3]=1; a[4]=2; qsort(a, 5, sizeof(a[0]), int_cmp); a[4]; }), "int a[5]; ...; qsort(a, 5, sizeof(a[0]), int_cmp); a[4];");
+    assert(3, ({ int a[5]; a[0]=5; a[1]=3; a[2]=4; a[3]=1; a[4]=2; qsort(a, 5, sizeof(a[0]), int_cmp); a[2]; }), "int a[5]; ...; qsort(a, 5, sizeof(a[0]), int_cmp); a[2];");
+
+    assert(1, sizeof(char), "sizeof(char)");
+    assert(2, sizeof(unsigned short), "sizeof(unsigned short)");
+    assert(4, sizeof(int), "sizeof(int)");
+    assert(8, sizeof(long), "sizeof(long)");
+    assert(8, sizeof(int *), "sizeof(int *)");
+    assert(12, sizeof(int) * 3, "sizeof(int) * 3");
+    assert(12, sizeof(int[3]), "sizeof(int[3])");
+    assert(48, sizeof(int[3][4]), "sizeof(int[3][4])");
+    assert(32, sizeof(char *[4]), "sizeof(char *[4])");
+    assert(8, sizeof(char (*)[4]), "sizeof(char (*)[4])");
+    assert(8, sizeof(int (*)(int)), "sizeof(int (*)(int))");
+    assert(16, sizeof(int (*[2])(int)), "sizeof(int (*[2])(int))");
+    assert(8, sizeof(struct point), "sizeof(struct point)");
+    assert(8, sizeof(struct {int a; char b;}), "sizeof(struct {int a; char b;})");
+    assert(16, sizeof(MyInt2), "sizeof(MyInt2)");
+    assert(4, sizeof(MyInt), "sizeof(MyInt)");
+    assert(4, ({ int x; sizeof(x); }), "int x; sizeof(x);");
+    assert(4, _Alignof(int[3]), "_Alignof(int[3])");
+    assert(8, _Alignof(char *[4]), "_Alignof(char *[4])");
+    assert(1, _Alignof(char[4]), "_Alignof(char[4])");
+    assert(3, ({ int a[2][4]; a[1][2]=3; (*(int (*)[4])a[1])[2]; }), "int a[2][4]; a[1][2]=3; (*(int (*)[4])a[1])[2];");
+    assert(4, ({ char a[sizeof(int)]; sizeof(a); }), "char a[sizeof(int)]; sizeof(a);");
+
+    assert(8, ({ int (*a)[3]; sizeof(a); }), "int (*a)[3]; sizeof(a);");
+    assert(12, ({ int (*a)[3]; sizeof(*a); }), "int (*a)[3]; sizeof(*a);");
+    assert(24, ({ int *a[3]; sizeof(a); }), "int *a[3]; sizeof(a);");
+    assert(8, ({ int *a[3]; sizeof(a[0]); }), "int *a[3]; sizeof(a[0]);");
+    assert(7, ({ int (*a)[3]; int b[2][3]; a=b; a[1][2]=7; b[1][2]; }), "int (*a)[3]; int b[2][3]; a=b; a[1][2]=7; b[1][2];");
+    assert(5, ({ int x=5; int *a[3]; a[1]=&x; *a[1]; }), "int x=5; int *a[3]; a[1]=&x; *a[1];");
+    assert(9, ({ g_rows[1][3]=9; (*row_of(1))[3]; }), "g_rows[1][3]=9; (*row_of(1))[3];");
+    assert(16, sizeof(*row_of(0)), "sizeof(*row_of(0))");
+    assert(64, sizeof(g_table), "sizeof(g_table)");
+    assert(98, ({ g_table[2]=str_later; g_table[2]()[1]; }), "g_table[2]=str_later; g_table[2]()[1];");
+    assert(7, pick_op(0)(3, 4), "pick_op(0)(3, 4)");
+    assert(8, ({ int (*(*pp)(int))(int, int); pp=pick_op; sizeof(pp); }), "int (*(*pp)(int))(int, int); pp=pick_op; sizeof(pp);");
+    assert(7, ({ int (*(*pp)(int))(int, int); pp=pick_op; pp(0)(3, 4); }), "int (*(*pp)(int))(int, int); pp=pick_op; pp(0)(3, 4);");
+    assert(4, ({ int ((x)); x=4; x; }), "int ((x)); x=4; x;");
+
+    assert(5, ({ const int x=5; x; }), "const int x=5; x;");
+    assert(5, ({ int const x=5; x; }), "int const x=5; x;");
+    assert(6, ({ const volatile int x=6; x; }), "const volatile int x=6; x;");
+    assert(7, ({ int y=7; const int *p=&y; *p; }), "int y=7; const int *p=&y; *p;");
+    assert(8, ({ int y=7; int *const p=&y; *p=8; y; }), "int y=7; int *const p=&y; *p=8; y;");
+    assert(9, ({ int a=1, b=9; const int *p=&a; p=&b; *p; }), "int a=1, b=9; const int *p=&a; p=&b; *p;");
+    assert(4, sizeof(const int), "sizeof(const int)");
+    assert(8, sizeof(int *const), "sizeof(int *const)");
+    assert(8, sizeof(const char *volatile *restrict), "sizeof(const char *volatile *restrict)");
+    assert(3, ({ struct {int a;} s; s.a=3; const struct {int a;} *p=(void *)&s; p->a; }), "struct {int a;} s; s.a=3; const struct {int a;} *p=(void *)&s; p->a;");
+    assert(2, ({ const MyInt x=2; x; }), "const MyInt x=2; x;");
+    assert(4, ({ volatile int x; x=4; x; }), "volatile int x; x=4; x;");
+    assert(5, ({ g_volatile=5; g_volatile; }), "g_volatile=5; g_volatile;");
+    assert(0, g_const, "g_const");
+    assert(6, ({ int a[3], b[3]; b[0]=1; b[1]=2; b[2]=3; copy_sum(a, b, 3); }), "int a[3], b[3]; ...; copy_sum(a, b, 3);");
+    assert(98, ({ const char *s="abc"; s[1]; }), "const char *s=\"abc\"; s[1];");
+
+    assert(5, sizeof(struct {char a; int :0; char b;}), "sizeof(struct {char a; int :0; char b;})");
+    assert(2, sizeof(struct {char a; int :3;}), "sizeof(struct {char a; int :3;})");
+    assert(4, sizeof(struct {char a; int b:3;}), "sizeof(struct {char a; int b:3;})");
+    assert(8, sizeof(struct {char a; long b:40;}), "sizeof(struct {char a; long b:40;})");
+    assert(8, sizeof(struct {int a:30; int b:4;}), "sizeof(struct {int a:30; int b:4;})");
+    assert(2, sizeof(struct {char a:4; char b:6;}), "sizeof(struct {char a:4; char b:6;})");
+    assert(4, sizeof(union {int a:3;}), "sizeof(union {int a:3;})");
+    assert(3, sizeof(union {char c; int :20;}), "sizeof(union {char c; int :20;})");
+    assert(9, sizeof(struct {char a; long :0; char b;}), "sizeof(struct {char a; long :0; char b;})");
+    assert(6, sizeof(struct {short a:9; short b:9; char c;}), "sizeof(struct {short a:9; short b:9; char c;})");
+    assert(2, sizeof(struct {unsigned char a:3, b:3, c:3;}), "sizeof(struct {unsigned char a:3, b:3, c:3;})");
+    assert(1, _Alignof(struct {char a; int :0; char b;}), "_Alignof(struct {char a; int :0; char b;})");
+    assert(4, _Alignof(struct {char a; int b:3;}), "_Alignof(struct {char a; int b:3;})");
+    assert(8, ({ struct {char a; long :0; char b;} x; (char *)&x.b - (char *)&x; }), "struct {char a; long :0; char b;} x; (char *)&x.b - (char *)&x;");
+    assert(7, ({ struct {unsigned a:3;} x; x.a=15; x.a; }), "struct {unsigned a:3;} x; x.a=15; x.a;");
+    assert(-1, ({ struct {int a:3;} x; x.a=7; x.a; }), "struct {int a:3;} x; x.a=7; x.a;");
+    assert(-4, ({ struct {int a:3;} x; x.a=4; }), "struct {int a:3;} x; x.a=4;");
+    assert(1, ({ struct {_Bool a:1;} x; x.a=2; x.a; }), "struct {_Bool a:1;} x; x.a=2; x.a;");
+    assert(-1, ({ struct {unsigned a:3;} x; x.a=0; x.a-1; }), "struct {unsigned a:3;} x; x.a=0; x.a-1;");
+    assert(123, ({ struct {int a:4; int b:20; int c:8;} x; x.a=-1; x.b=123; x.c=-1; x.b; }), "struct {int a:4; int b:20; int c:8;} x; x.a=-1; x.b=123; x.c=-1; x.b;");
+    assert(-1, ({ struct {int a:4; int b:20; int c:8;} x; x.a=-1; x.b=123; x.c=-1; x.a; }), "struct {int a:4; int b:20; int c:8;} x; ...; x.a;");
+    assert(18, ({ struct {unsigned char a:4, b:4;} x; x.a=2; x.b=1; *(unsigned char *)&x; }), "struct {unsigned char a:4, b:4;} x; x.a=2; x.b=1; *(unsigned char *)&x;");
+    assert(2, ({ struct {long a:40; long b:24;} x; x.a=-123456789012; x.b=5; (x.a==-123456789012) + (x.b==5); }), "struct {long a:40; long b:24;} x; ...");
+    assert(5, ({ BF x; gcc_bf_fill(&x); (x.a==5) + (x.b==-7) + (x.c==9) + (x.d==-123456789012) + (x.e==1); }), "BF x; gcc_bf_fill(&x); ...");
+    assert(1, ({ BF x; gcc_bf_fill(&x); x.a=6; x.b=11; x.c=3; x.d=98765432109; x.e=0; gcc_bf_check(&x); }), "BF x; ...; gcc_bf_check(&x);");
+
+    assert(12, ({ int a[]={1,2,3}; sizeof(a); }), "int a[]={1,2,3}; sizeof(a);");
+    assert(3, ({ int a[]={1,2,3}; a[2]; }), "int a[]={1,2,3}; a[2];");
+    assert(4, ({ char s[]="abc"; sizeof(s); }), "char s[]=\"abc\"; sizeof(s);");
+    assert(99, ({ char s[]="abc"; s[2]; }), "char s[]=\"abc\"; s[2];");
+    assert(0, ({ char s[]="abc"; s[3]; }), "char s[]=\"abc\"; s[3];");
+    assert(0, ({ char s[8]="abc"; s[7]; }), "char s[8]=\"abc\"; s[7];");
+    assert(98, ({ char s[2]="abc"; s[1]; }), "char s[2]=\"abc\"; s[1];");
+    assert(0, ({ int a[4]={1,2}; a[3]; }), "int a[4]={1,2}; a[3];");
+    assert(2, ({ int a[4]={1,2,}; a[1]; }), "int a[4]={1,2,}; a[1];");
+    assert(0, ({ int a[3]={}; a[0]+a[1]+a[2]; }), "int a[3]={}; a[0]+a[1]+a[2];");
+    assert(16, ({ int a[][2]={{1,2},{3,4}}; sizeof(a); }), "int a[][2]={{1,2},{3,4}}; sizeof(a);");
+    assert(4, ({ int a[][2]={{1,2},{3,4}}; a[1][1]; }), "int a[][2]={{1,2},{3,4}}; a[1][1];");
+    assert(0, ({ int a[2][2]={{1},{3}}; a[0][1]; }), "int a[2][2]={{1},{3}}; a[0][1];");
+    assert(5, ({ int x={5}; x; }), "int x={5}; x;");
+    assert(3, ({ int x=1; int a[]={x, x+1, x+2}; a[2]; }), "int x=1; int a[]={x, x+1, x+2}; a[2];");
+    assert(7, ({ struct {int a; char b; long c;} s={1,2,4}; s.a+s.b+s.c; }), "struct {int a; char b; long c;} s={1,2,4}; s.a+s.b+s.c;");
+    assert(0, ({ struct {int a; int b;} s={1}; s.b; }), "struct {int a; int b;} s={1}; s.b;");
+    assert(6, ({ struct {int a:3; int :2; int b:5;} s={-1,6}; s.a+s.b+1; }), "struct {int a:3; int :2; int b:5;} s={-1,6}; s.a+s.b+1;");
+    assert(3, ({ union {char a; int b;} u={3}; u.b; }), "union {char a; int b;} u={3}; u.b;");
+    assert(4, ({ struct {int a[2]; int b;} s={{1,2},4}; s.b; }), "struct {int a[2]; int b;} s={{1,2},4}; s.b;");
+    assert(2, ({ struct T {int a;} t={2}; struct T s=t; s.a; }), "struct T {int a;} t={2}; struct T s=t; s.a;");
+    assert(8, ({ const int a[]={8}; a[0]; }), "const int a[]={8}; a[0];");
+    assert(12, sizeof(g_init), "sizeof(g_init)");
+    assert(3, g_init[2], "g_init[2]");
+    assert(6, sizeof(g_str), "sizeof(g_str)");
+    assert(108, g_str[3], "g_str[3]");
+    assert(3, g_grid[0][2], "g_grid[0][2]");
+    assert(4, g_grid[1][0], "g_grid[1][0]");
+    assert(0, g_grid[1][2], "g_grid[1][2]");
+    assert(1, g_struct.a, "g_struct.a");
+    assert(-2, g_struct.b, "g_struct.b");
+    assert(15, g_struct.c, "g_struct.c");
+    assert(3, g_struct.d, "g_struct.d");
+    assert(-7, g_long, "g_long");
+    assert(9, g_const_init, "g_const_init");
+    assert(30, gcc_tbl[2], "gcc_tbl[2]");
+    assert(40, ({ extern int gcc_tbl[]; gcc_tbl[3]; }), "extern int gcc_tbl[]; gcc_tbl[3];");
+    assert(6, ({ int a[]={1,2,3}; arr_sum(a, 3); }), "int a[]={1,2,3}; arr_sum(a, 3);");
+    assert(0, ({ arr_sum((long)0, 0); }), "arr_sum((long)0, 0);");
+    assert(1, ({ int *p=(char)(long)0; p==0; }), "int *p=(char)(long)0; p==0;");
+    assert(4, sizeof(struct {int n; int a[];}), "sizeof(struct {int n; int a[];})");
+    assert(8, sizeof(struct {char c; long a[];}), "sizeof(struct {char c; long a[];})");
+    assert(8, _Alignof(struct {char c; long a[];}), "_Alignof(struct {char c; long a[];})");
+    assert(6, ({ long buf[4]; struct {int n; int a[];} *p=(void *)buf; p->n=2; p->a[0]=5; p->a[1]=6; p->a[1]; }), "struct {int n; int a[];} *p=(void *)buf; ...; p->a[1];");
+    assert(4, ({ long buf[4]; struct {int n; int a[];} *p=(void *)buf; (char *)p->a - (char *)p; }), "(char *)p->a - (char *)p");
+
+    assert(20, ({ int n=5; int a[n]; sizeof(a); }), "int n=5; int a[n]; sizeof(a);");
+    assert(7, ({ int n=5; int a[n]; a[4]=7; a[4]; }), "int n=5; int a[n]; a[4]=7; a[4];");
+    assert(60, ({ int n=5, m=3; int a[n][m]; sizeof(a); }), "int n=5, m=3; int a[n][m]; sizeof(a);");
+    assert(12, ({ int n=5, m=3; int a[n][m]; sizeof(a[0]); }), "int n=5, m=3; int a[n][m]; sizeof(a[0]);");
+    assert(42, ({ int n=5, m=3; int a[n][m]; a[4][2]=42; a[1][1]=1; a[4][2]; }), "int n=5, m=3; int a[n][m]; a[4][2]=42; ...; a[4][2];");
+    assert(6, ({ int n=5, m=3; int a[n][m]; &a[3][0] - &a[1][0]; }), "int n=5, m=3; int a[n][m]; &a[3][0] - &a[1][0];");
+    assert(40, ({ int n=5; int a[n][2]; sizeof(a); }), "int n=5; int a[n][2]; sizeof(a);");
+    assert(24, ({ int n=3; int a[2][n]; sizeof(a); }), "int n=3; int a[2][n]; sizeof(a);");
+    assert(40, ({ int n=5; sizeof(int[n][2]); }), "int n=5; sizeof(int[n][2]);");
+    assert(16, ({ int n=2; long a[n]; sizeof(a); }), "int n=2; long a[n]; sizeof(a);");
+    assert(4, ({ int n=5; int a[n]; int *p=a; p[2]=4; a[2]; }), "int n=5; int a[n]; int *p=a; p[2]=4; a[2];");
+    assert(3, ({ int n=3; int (*p)[n]; sizeof(*p) / sizeof(int); }), "int n=3; int (*p)[n]; sizeof(*p) / sizeof(int);");
+    assert(25, vla_fill(4), "vla_fill(4)");
+    assert(4, vla_param_elem(3, 0), "vla_param_elem(3, 0)");
+    assert(112, ({ int x[2][3]; x[1][2]=100; vla_param_row(2, 3, x); }), "int x[2][3]; x[1][2]=100; vla_param_row(2, 3, x);");
+    assert(220, ({ int x[4][5]; x[1][2]=200; vla_param_row(4, 5, x); }), "int x[4][5]; x[1][2]=200; vla_param_row(4, 5, x);");
+    assert(10, vla_param_rows(5, 0), "vla_param_rows(5, 0)");
+    assert(1, ({ int n=1000; long d=stack_probe(); int i=0; while (i<100) { int a[n]; a[0]=i; i=i+1; } long e=stack_probe(); e==d; }), "int a[n] in a loop frees its stack");
+    assert(1, ({ long d=stack_probe(); int x=({ int n=3; char c[n]; c[2]=7; c[2]; }); long e=stack_probe(); e==d; }), "int a[n] in a statement expression frees its stack");
+    assert(10, ({ int k=3; char c[k]; c[2]=7; c[2]+sizeof(c); }), "int k=3; char c[k]; c[2]=7; c[2]+sizeof(c);");
+    assert(1, 1.5 == 1.5, "1.5 == 1.5");
+    assert(0, 1.5 == 1.25, "1.5 == 1.25");
+    assert(3, (int)3.99, "(int)3.99");
+    assert(-3, (int)-3.99, "(int)-3.99");
+    assert(1, .5 + 1e1 == 10.5, ".5 + 1e1 == 10.5");
+    assert(1, 2.5e-1 == 0.25, "2.5e-1 == 0.25");
+    assert(4, sizeof(1.0f), "sizeof(1.0f)");
+    assert(8, sizeof(1.0), "sizeof(1.0)");
+    assert(4, ({ float x; sizeof(x); }), "float x; sizeof(x);");
+    assert(8, ({ double x; sizeof(x); }), "double x; sizeof(x);");
+    assert(8, sizeof(1.0f + 1.0), "sizeof(1.0f + 1.0)");
+    assert(4, sizeof(1.0f + 1), "sizeof(1.0f + 1)");
+    assert(8, sizeof(1.0 + (char)1), "sizeof(1.0 + (char)1)");
+    assert(7, ({ double x=3.5; (int)(x * 2); }), "double x=3.5; (int)(x * 2);");
+    assert(1, ({ float x=0.1f; double y=0.1; x != y; }), "float x=0.1f; double y=0.1; x != y;");
+    assert(1, ({ float x=0.1f; double y=x; x == y; }), "float x=0.1f; double y=x; x == y;");
+    assert(5, ({ double x=10; float y=4; (int)(x / y * 2); }), "double x=10; float y=4; (int)(x / y * 2);");
+    assert(-1, ({ double x=1.5; (int)(x - 2.5); }), "double x=1.5; (int)(x - 2.5);");
+    assert(1, ({ double x=-2.5; x < -2; }), "double x=-2.5; x < -2;");
+    assert(2, ({ double x=1; (x < 2) + (x <= 1) + (2 < x) + (1.5 <= x); }), "double x=1; (x < 2) + (x <= 1) + (2 < x) + (1.5 <= x);");
+    assert(1, ({ double x=1; (x > 0.5) + (x >= 1.5); }), "double x=1; (x > 0.5) + (x >= 1.5);");
+    assert(1, ({ double nan=0.0/0.0; (nan == nan) + (nan < 1) + (nan <= 1) + (nan > 1) + (nan >= 1) + (nan != nan); }), "double nan=0.0/0.0; ...");
+    assert(1, ({ double nan=0.0/0.0; (_Bool)nan; }), "double nan=0.0/0.0; (_Bool)nan;");
+    assert(0, (_Bool)0.0, "(_Bool)0.0");
+    assert(1, (_Bool)0.1, "(_Bool)0.1");
+    assert(2, ({ double x=-0.0; int r=1; if (x) r=0; else r=2; r; }), "double x=-0.0; if (x) ...");
+    assert(3, ({ double x=3; int n=0; while (x) { x = x - 1; n = n + 1; } n; }), "double x=3; while (x) ...");
+    assert(1, ({ double nan=0.0/0.0; int r=0; if (nan) r=1; r; }), "double nan=0.0/0.0; if (nan) ...");
+    assert(1, ({ unsigned long u=18446744073709549568UL; double d=u; d == 18446744073709549568.0; }), "unsigned long u=18446744073709549568UL; double d=u;");
+    assert(1, ({ double d=18446744073709549568.0; unsigned long u=d; u == 18446744073709549568UL; }), "double d=18446744073709549568.0; unsigned long u=d;");
+    assert(1, ({ float f=9223372036854775808.0f; unsigned long u=f; u == 9223372036854775808UL; }), "float f=9223372036854775808.0f; unsigned long u=f;");
+    assert(1, ({ unsigned long u=9223372036854775809UL; float f=u; f == 9223372036854775808.0f; }), "unsigned long u=9223372036854775809UL; float f=u;");
+    assert(-1, ({ unsigned u=4294967295U; double d=u; (int)(d - 4294967296.0); }), "unsigned u=4294967295U; double d=u;");
+    assert(-56, ({ double d=200; signed char c=(int)d; c; }), "double d=200; signed char c=(int)d;");
+    assert(3, ({ double d=3.7; char c=d; c; }), "double d=3.7; char c=d;");
+    assert(1, ({ float f=16777217; f == 16777216; }), "float f=16777217; f == 16777216;");
+    assert(1, ({ long l=-5; double d=l; d == -5.0; }), "long l=-5; double d=l;");
+    assert(1, ({ double a[3]; a[0]=0.5; a[1]=a[0] * 3; a[2]=a[1] - a[0]; a[2] == 1; }), "double a[3]; ...");
+    assert(1, ({ struct { char c; float f; double d; } s; s.f=2.5; s.d=s.f * 2; s.d == 5; }), "struct { char c; float f; double d; } s; ...");
+    assert(1, ({ double x=2.5; double *p=&x; *p=*p * 2; x == 5; }), "double x=2.5; double *p=&x; *p=*p * 2;");
+    assert(1, ({ double x=1; double y=x=4.5; y == 4.5; }), "double x=1; double y=x=4.5;");
+    assert(1, ({ float x[2] = {1.5, 2}; double y[] = {x[0], x[1] / 4, 3}; (y[0] == 1.5) + (y[1] == 0.5) + (sizeof(y) == 24) == 3; }), "float x[2] = {1.5, 2}; double y[] = ...");
+    assert(1, g_double == 6, "g_double == 6");
+    assert(1, (g_floats[0] == 0.5) + (g_floats[1] == 1) + (g_floats[2] == 2.5) == 3, "g_floats[] == {0.5, 1, 2.5}");
+    assert(2, g_from_double, "g_from_double");
+    assert(1, fneg(2.5) == -2.5, "fneg(2.5) == -2.5");
+    assert(1, ({ double x=0.0; 1 / -x < 0; }), "double x=0.0; 1 / -x < 0;");
+    assert(1, ({ float x=0; 1 / -x < 0; }), "float x=0; 1 / -x < 0;");
+    assert(1, ({ double x=-0.0; 1 / x < 0; }), "double x=-0.0; 1 / x < 0;");
+    assert(1, 1 / g_neg_zero < 0, "1 / g_neg_zero < 0");
+    assert(1, ({ double x=1.5; -x == -1.5; }), "double x=1.5; -x == -1.5;");
+    assert(1, fdiv(1, 4) == 0.25, "fdiv(1, 4) == 0.25");
+    assert(1, gcc_fadd(1.5, 2.25, 3) == 6.75, "gcc_fadd(1.5, 2.25, 3) == 6.75");
+    assert(1, gcc_fmul(1.5, 4) == 6, "gcc_fmul(1.5, 4) == 6");
+    assert(385, gcc_fmany(1, 2, 3, 4, 5, 6, 7, 8, 9, 10), "gcc_fmany(1, 2, 3, 4, 5, 6, 7, 8, 9, 10)");
+    assert(123456, gcc_fmix(1, 2, 3, 4, 5, 6), "gcc_fmix(1, 2, 3, 4, 5, 6)");
+    assert(1, ({ SD x; x.a=2.5; gcc_sd(x) == 2.5; }), "SD x; x.a=2.5; gcc_sd(x) == 2.5;");
+    assert(1, ({ SFF x; x.a=1.5; x.b=2.5; gcc_sff(x) == 17.5; }), "SFF x; x.a=1.5; x.b=2.5; gcc_sff(x) == 17.5;");
+    assert(1, ({ SFID x; x.a=1.5; x.b=2; x.c=3.5; gcc_sfid(x) == 173.5; }), "SFID x; x.a=1.5; x.b=2; x.c=3.5; gcc_sfid(x) == 173.5;");
+    assert(1, ({ SDL x; x.a=1.5; x.b=2; gcc_sdl(x) == 17; }), "SDL x; x.a=1.5; x.b=2; gcc_sdl(x) == 17;");
+    assert(1, ({ SFF x=gcc_ret_sff(); (x.a == 1.5) + (x.b == 2.5) == 2; }), "SFF x=gcc_ret_sff(); ...");
+    assert(1, ({ SFID x=gcc_ret_sfid(); (x.a == 3.5) + (x.b == 4) + (x.c == 5.5) == 3; }), "SFID x=gcc_ret_sfid(); ...");
+    assert(1, ({ SDL x=gcc_ret_sdl(); (x.a == 6.5) + (x.b == 7) == 2; }), "SDL x=gcc_ret_sdl(); ...");
+    assert(1, gcc_vsum(3, 1.5, 2.5f, 3.0) == 7, "gcc_vsum(3, 1.5, 2.5f, 3.0) == 7");
+    assert(1, gcc_vsum(10, 1., 2., 3., 4., 5., 6., 7., 8., 9., 10.) == 55, "gcc_vsum(10, 1., 2., ..., 10.) == 55");
+    assert(1, gcc_call_qcc_float() == 123123469, "gcc_call_qcc_float() == 123123469");
+    assert(0, ({ char buf[32]; sprintf(buf, "%.2f %d %.1f", 1.5, 7, 2.25f); strcmp(buf, "1.50 7 2.2"); }), "sprintf(buf, \"%.2f %d %.1f\", 1.5, 7, 2.25f)");
+    assert(16, sizeof(long double), "sizeof(long double)");
+    assert(16, _Alignof(long double), "_Alignof(long double)");
+    assert(16, sizeof(1.0L), "sizeof(1.0L)");
+    assert(16, sizeof(1.0L + 1), "sizeof(1.0L + 1)");
+    assert(16, sizeof(1.0 + 1.0l), "sizeof(1.0 + 1.0l)");
+    assert(32, sizeof(struct { char c; long double x; }), "sizeof(struct { char c; long double x; })");
+    assert(1, 1.5L == 1.5, "1.5L == 1.5");
+    assert(3, (int)3.99L, "(int)3.99L");
+    assert(-3, (int)-3.99L, "(int)-3.99L");
+    assert(1, ({ long double x=1; x + 1e-18 != 1; }), "long double x=1; x + 1e-18 != 1;");
+    assert(1, ({ double x=1; x + 1e-18 == 1; }), "double x=1; x + 1e-18 == 1;");
+    assert(1, ({ long double x=1; long double y=3; x / y * 3 == 1; }), "long double x=1; long double y=3; x / y * 3 == 1;");
+    assert(7, ({ long double x=3.5; (int)(x * 2); }), "long double x=3.5; (int)(x * 2);");
+    assert(-1, ({ long double x=1.5; (int)(x - 2.5); }), "long double x=1.5; (int)(x - 2.5);");
+    assert(5, ({ long double x=10; float y=4; (int)(x / y * 2); }), "long double x=10; float y=4; (int)(x / y * 2);");
+    assert(3, ({ long double x=10; int y=4; (int)(y - x / y + 1.5); }), "long double x=10; int y=4; (int)(y - x / y + 1.5);");
+    assert(2, ({ long double x=1; (x < 2) + (x <= 1) + (2 < x) + (1.5 <= x); }), "long double x=1; (x < 2) + (x <= 1) + (2 < x) + (1.5 <= x);");
+    assert(1, ({ long double x=1; (x > 0.5) + (x >= 1.5); }), "long double x=1; (x > 0.5) + (x >= 1.5);");
+    assert(1, ({ long double nan=0.0L/0.0L; (nan == nan) + (nan < 1) + (nan <= 1) + (nan > 1) + (nan >= 1) + (nan != nan); }), "long double nan=0.0L/0.0L; ...");
+    assert(1, ({ long double nan=0.0L/0.0L; (_Bool)nan; }), "long double nan=0.0L/0.0L; (_Bool)nan;");
+    assert(0, (_Bool)0.0L, "(_Bool)0.0L");
+    assert(2, ({ long double x=-0.0L; int r=1; if (x) r=0; else r=2; r; }), "long double x=-0.0L; if (x) ...");
+    assert(3, ({ long double x=3; int n=0; while (x) { x = x - 1; n = n + 1; } n; }), "long double x=3; while (x) ...");
+    assert(1, ({ unsigned long u=18446744073709551615UL; long double x=u; x == 18446744073709551615.0L; }), "unsigned long u=18446744073709551615UL; long double x=u; x == 18446744073709551615.0L;");
+    assert(1, ({ unsigned long u=18446744073709551615UL; long double x=u; (unsigned long)x == u; }), "unsigned long u=18446744073709551615UL; (unsigned long)(long double)u == u;");
+    assert(1, ({ long double x=9223372036854775808.0L; unsigned long u=x; u == 9223372036854775808UL; }), "long double x=9223372036854775808.0L; unsigned long u=x;");
+    assert(1, ({ long l=-9223372036854775807; long double x=l; (long)x == l; }), "long l=-9223372036854775807; (long)(long double)l == l;");
+    assert(-56, ({ long double x=200; signed char c=(int)x; c; }), "long double x=200; signed char c=(int)x;");
+    assert(1, ({ double d=0.1; long double x=d; double e=x; (x == d) + (e == d) == 2; }), "double d=0.1; long double x=d; double e=x;");
+    assert(1, ({ float f=0.1f; long double x=f; float g=x; (x == f) + (g == f) == 2; }), "float f=0.1f; long double x=f; float g=x;");
+    assert(1, ({ long double a[3]; a[0]=0.5; a[1]=a[0] * 3; a[2]=a[1] - a[0]; a[2] == 1; }), "long double a[3]; ...");
+    assert(1, ({ struct { char c; long double x; int i; } s; s.x=2.5; s.i=s.x * 2; s.i == 5; }), "struct { char c; long double x; int i; } s; ...");
+    assert(1, ({ long double x=2.5; long double *p=&x; *p=*p * 2; x == 5; }), "long double x=2.5; long double *p=&x; *p=*p * 2;");
+    assert(1, ({ long double x=1; long double y=x=4.5; y == 4.5; }), "long double x=1; long double y=x=4.5;");
+    assert(1, ({ long double x=0; 1 / -x < 0; }), "long double x=0; 1 / -x < 0;");
+    assert(1, ({ long double x=2.5; -x == -2.5; }), "long double x=2.5; -x == -2.5;");
+    assert(1, ({ long double x=0; int i; for (i=0; i<4; i=i+1) x = x + 0.25; x == 1; }), "long double x=0; for (...) x = x + 0.25;");
+    assert(1, g_ldouble == 6, "g_ldouble == 6");
+    assert(1, (g_ldoubles[0] == 0.5) + (g_ldoubles[1] == 1) + (g_ldoubles[2] == 2.5) == 3, "g_ldoubles[] == {0.5, 1, 2.5}");
+    assert(2, g_from_ldouble, "g_from_ldouble");
+    assert(1, 0.1L == gcc_ld_tenth(), "0.1L == gcc_ld_tenth()");
+    assert(1, 0.1L != 0.1, "0.1L != 0.1");
+    assert(1, g_ld_tenth == gcc_ld_tenth(), "g_ld_tenth == gcc_ld_tenth()");
+    assert(1, g_ld_neg_tenth == -gcc_ld_tenth(), "g_ld_neg_tenth == -gcc_ld_tenth()");
+    assert(1, (1e-4950L > 0) + (1e-4950L == gcc_ld_tiny()) == 2, "(1e-4950L > 0) + (1e-4950L == gcc_ld_tiny()) == 2");
+    assert(1, (1e4000L < 1 / 0.0L) + (1e4000L == gcc_ld_huge()) == 2, "(1e4000L < 1 / 0.0L) + (1e4000L == gcc_ld_huge()) == 2");
+    assert(1, ld_half(5) == 2.5, "ld_half(5) == 2.5");
+    assert(1, gcc_ld_add(1.5, 2, 3) == 321.5, "gcc_ld_add(1.5, 2, 3) == 321.5");
+    assert(654321, gcc_ld_many(1, 2, 3, 4, 5, 6), "gcc_ld_many(1, 2, 3, 4, 5, 6)");
+    assert(1, ({ SLD x; x.a=2.5; gcc_sld(x) == 2.5; }), "SLD x; x.a=2.5; gcc_sld(x) == 2.5;");
+    assert(1, ({ SCLD x; x.a=3; x.b=1.5; gcc_scld(x) == 31.5; }), "SCLD x; x.a=3; x.b=1.5; gcc_scld(x) == 31.5;");
+    assert(1, gcc_ret_sld().a == 2.5, "gcc_ret_sld().a == 2.5");
+    assert(1, ({ SCLD x=gcc_ret_scld(); (x.a == 3) + (x.b == 4.5) == 2; }), "SCLD x=gcc_ret_scld(); ...");
+    assert(1, gcc_vsum_ld(3, 1.5L, 2.5L, 3.0L) == 7, "gcc_vsum_ld(3, 1.5L, 2.5L, 3.0L) == 7");
+    assert(1, gcc_call_qcc_ld() == 782654321, "gcc_call_qcc_ld() == 782654321");
+    assert(0, ({ char buf[32]; sprintf(buf, "%.3Lf %d %.1Lf", 1.5L, 7, 2.25L); strcmp(buf, "1.500 7 2.2"); }), "sprintf(buf, \"%.3Lf %d %.1Lf\", 1.5L, 7, 2.25L)");
 
     printf("OK\n");
     return 0;
 }
+
+int point_sum(struct point *p)
+{
+    return p->x + p->y;
+}
+
+int add_later(int x, int y)
+{
+    return x + y;
+}
+
+char *str_later(void)
+{
+    return "abc";
+}
+
+long long_later(void)
+{
+    return 4294967296;
+}
+
+int sub_unproto(int a, int b)
+{
+    return a - b;
+}
+
+double half_unproto(double x)
+{
+    return x / 2;
+}
+
+S12 y_of(S12 x)
+{
+    return x;
+}
+
+S3 qcc_ret_s3(void)
+{
+    S3 x;
+    x.a = 1;
+    x.b = 2;
+    x.c = 3;
+    return x;
+}
+
+S12 qcc_ret_s12(void)
+{
+    S12 x;
+    x.a = 4;
+    x.b = 5;
+    x.c = 6;
+    return x;
+}
+
+S24 qcc_ret_s24(long n)
+{
+    S24 x;
+    x.a = n;
+    x.b = n * 2;
+    x.c = n * 3;
+    return x;
+}
+
+long qcc_s24(S24 x)
+{
+    return x.a * 100 + x.b * 10 + x.c;
+}
+
+long qcc_spill(long a, long b, long c, long d, long e, S16 x, long f)
+{
+    return a + b + c + d + e + x.a * 1000 + x.b * 100 + f * 10;
+}
+
+int twice(int x)
+{
+    return x * 2;
+}
+
+int apply(int (*f)(int), int x)
+{
+    return f(x);
+}
+
+int apply_fn(int f(int), int x)
+{
+    return f(x);
+}
+
+int int_cmp(void *a, void *b)
+{
+    return *(int *)a - *(int *)b;
+}
+
+int (*row_of(int n))[4]
+{
+    return &g_rows[n];
+}
+
+int (*pick_op(int n))(int, int)
+{
+    return add2;
+}
+
+long copy_sum(int *restrict dst, const int *restrict src, int n)
+{
+    long sum = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1) {
+        dst[i] = src[i];
+        sum = sum + dst[i];
+    }
+    return sum;
+}
+
+int arr_sum(int a[], int n)
+{
+    int sum = 0;
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        sum = sum + a[i];
+    return sum;
+}
+
+long stack_probe(void)
+{
+    int x;
+    return (long)&x;
+}
+
+int vla_fill(int n)
+{
+    int a[n];
+    int i;
+    for (i = 0; i < n; i = i + 1)
+        a[i] = i * i;
+    return a[n - 1] + sizeof(a);
+}
+
+double fdiv(double a, double b)
+{
+    return a / b;
+}
+
+float fneg(float x)
+{
+    return -x;
+}
+
+double qcc_fmix(int a, double b, long c, float d, char e, double f)
+{
+    return a * 100000 + b * 10000 + c * 1000 + d * 100 + e * 10 + f;
+}
+
+SFID qcc_ret_sfid(void)
+{
+    SFID x;
+    x.a = 3.5;
+    x.b = 4;
+    x.c = 5.5;
+    return x;
+}
+
+double qcc_sfid(SFID x)
+{
+    return x.a * 100 + x.b * 10 + x.c;
+}
+
+long double ld_half(long double x)
+{
+    return x / 2;
+}
+
+long double qcc_ld_many(int a, long double b, double c, int d, long double e, float f)
+{
+    return a + b * 10 + c * 100 + d * 1000 + e * 10000 + f * 100000;
+}
+
+SLD qcc_ret_sld(void)
+{
+    SLD x;
+    x.a = 2;
+    return x;
+}
+
+long double qcc_scld(SCLD x)
+{
+    return x.a * 10 + x.b;
+}
+
+int vla_param_elem(int n, int a[n])
+{
+    return sizeof(*a);
+}
+
+int vla_param_row(int n, int m, int a[n][m])
+{
+    return sizeof(a[0]) + a[1][2];
+}
+
+long vla_param_rows(int n, long a[][n])
+{
+    return &a[2][0] - &a[0][0];
+}
+
+long aligned_local(long a, long b, long c, long d, long e, long f, long g, S16 x)
+{
+    _Alignas(64) char buf[3];
+    buf[2] = 9;
+    if ((long)buf - (long)buf / 64 * 64)
+        return -1;
+    return x.a * 1000 + x.b * 100 + (a + b + c + d + e + f + g - 28) + g * 10 + buf[2];
+}